@@ -0,0 +1,179 @@
+use super::{Observer, CPU, X};
+
+pub const SCREEN_WIDTH: usize   = 40;
+pub const SCREEN_HEIGHT: usize  = 6;
+
+const LETTER_WIDTH: usize   = 4;
+const LETTER_HEIGHT: usize  = 6;
+const LETTER_SPACING: usize = 1;
+
+/// The letters of the Advent of Code font, four pixels wide and six pixels tall.
+const FONT: [(char, [&str; LETTER_HEIGHT]); 18] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#..#", "#..#", ".###", "...#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+/// The screen of the handheld device, filled in one pixel per cycle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crt {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<bool>,
+}
+
+impl Default for Crt {
+    fn default() -> Crt {
+        Crt::new(SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+}
+
+impl Crt {
+    pub fn new(width: usize, height: usize) -> Crt {
+        Crt { width, height, pixels: vec![false; width * height] }
+    }
+
+    /// Reads a screen drawn with `#` for lit and `.` for dark pixels, one row per line.
+    pub fn from_text(s: &str) -> Option<Crt> {
+        let rows = s.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+        let width = rows.first()?.len();
+
+        if rows.iter().any(|row| row.len() != width || row.chars().any(|c| c != '#' && c != '.')) {
+            return None;
+        }
+
+        Some(Crt { width, height: rows.len(), pixels: rows.iter().flat_map(|row| row.chars().map(|c| c == '#')).collect() })
+    }
+
+    /// Writes `text` in the Advent of Code font, on a screen at least as wide as the standard one.
+    pub fn from_letters(text: &str) -> Option<Crt> {
+        let stride = LETTER_WIDTH + LETTER_SPACING;
+        let mut crt = Crt::new(SCREEN_WIDTH.max(text.chars().count() * stride), LETTER_HEIGHT);
+
+        for (index, letter) in text.chars().enumerate() {
+            let (_, rows) = FONT.iter().find(|(x, _)| *x == letter.to_ascii_uppercase())?;
+
+            for (y, row) in rows.iter().enumerate() {
+                for (dx, c) in row.chars().enumerate() {
+                    crt.pixels[index * stride + dx + y * crt.width] = c == '#';
+                }
+            }
+        }
+
+        Some(crt)
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.pixels[x + y * self.width]
+    }
+
+    pub fn render_text(&self) -> String {
+        self.pixels.chunks(self.width)
+            .map(|row| row.iter().map(|&lit| if lit { '#' } else { '.' }).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the screen as a binary PBM image, with every pixel drawn as a `scale` by `scale` square.
+    pub fn render_pbm(&self, scale: usize) -> Vec<u8> {
+        let (width, height) = (self.width * scale, self.height * scale);
+        let mut output = format!("P4\n{} {}\n", width, height).into_bytes();
+
+        for y in 0..height {
+            let row = (0..width).map(|x| self.pixel(x / scale, y / scale)).collect::<Vec<_>>();
+
+            output.extend(row.chunks(8).map(|bits| {
+                bits.iter().enumerate().fold(0u8, |byte, (index, &lit)| byte | ((lit as u8) << (7 - index)))
+            }));
+        }
+
+        output
+    }
+
+    fn letter_at(&self, x: usize) -> char {
+        if (0..LETTER_HEIGHT).all(|y| (x..x + LETTER_WIDTH).all(|x| !self.pixel(x, y))) { return ' ' }
+
+        FONT.iter()
+            .find(|(_, rows)| rows.iter().enumerate().all(|(y, row)| {
+                row.chars().enumerate().all(|(dx, c)| (c == '#') == self.pixel(x + dx, y))
+            }))
+            .map(|(letter, _)| *letter)
+            .unwrap_or('?')
+    }
+
+    /// Reads the screen as letters of the Advent of Code font, using `?` for anything it does not recognise.
+    /// Blank cells read as spaces, and those after the last letter are dropped.
+    pub fn read_letters(&self) -> String {
+        if self.height != LETTER_HEIGHT { return String::new() }
+
+        let letters = (0..)
+            .map(|index| index * (LETTER_WIDTH + LETTER_SPACING))
+            .take_while(|x| x + LETTER_WIDTH <= self.width)
+            .map(|x| self.letter_at(x))
+            .collect::<String>();
+
+        letters.trim_end().to_string()
+    }
+}
+
+impl Observer for Crt {
+    /// Lights the pixel being drawn during this cycle when the three pixel wide sprite, centred on X, covers it.
+    fn on_tick(&mut self, cpu: &CPU) {
+        if self.pixels.is_empty() { return }
+
+        let index = (cpu.current_tick - 1) % self.pixels.len();
+        let column = (index % self.width) as isize;
+
+        self.pixels[index] = (column - cpu.registers[X]).abs() <= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{draw, Cycle, InstructionSet};
+
+    #[test]
+    fn letters_round_trip_without_trailing_cells() {
+        assert_eq!(Crt::from_letters("ZHEB").unwrap().read_letters(), "ZHEB");
+        assert_eq!(Crt::from_letters("abcefghi").unwrap().read_letters(), "ABCEFGHI");
+        assert_eq!(Crt::from_letters("JKLOPRSUYZ").unwrap().read_letters(), "JKLOPRSUYZ");
+        assert_eq!(Crt::default().read_letters(), "");
+    }
+
+    #[test]
+    fn unknown_and_blank_cells() {
+        let mut crt = Crt::from_letters("AB").unwrap();
+        crt.pixels[0] = true;
+
+        assert_eq!(crt.read_letters(), "?B");
+
+        let mut crt = Crt::from_letters("A").unwrap();
+        crt.pixels[2 * (LETTER_WIDTH + LETTER_SPACING)] = true;
+
+        assert_eq!(crt.read_letters(), "A ?");
+    }
+
+    #[test]
+    fn puzzle_input_reads_as_letters() {
+        let instruction_set = InstructionSet::standard();
+        let cycles = Cycle::assemble(include_str!("input.txt"), &instruction_set).unwrap();
+
+        assert_eq!(draw(&instruction_set, &cycles).read_letters(), "ZGCJZJFL");
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::{self, BufRead, Write};
+
+use super::{crt::Crt, Cycle, InstructionSet, CPU, MAX_TICKS, REGISTER_NAMES};
+
+const HELP: &str = "\
+commands:
+  step [n]              run n cycles (default 1)
+  next [n]              run n whole instructions (default 1)
+  continue              run until a breakpoint, the end of the program or 1000000 cycles
+  break cycle <n>       stop during cycle n
+  break <register> <v>  stop during the cycle where the register changes to v
+  breakpoints           list breakpoints
+  delete <index>        remove a breakpoint
+  print                 show the registers, cycle and current instruction
+  crt                   show what has been drawn so far
+  list                  show the instructions around the current one
+  reset                 start the program over
+  quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Breakpoint {
+    Cycle(usize),
+    Register(usize, isize),
+}
+
+impl Breakpoint {
+    fn parse(args: &[&str]) -> Option<Breakpoint> {
+        match args {
+            ["cycle", cycle] => Some(Breakpoint::Cycle(cycle.parse().ok()?)),
+            [register, value] => {
+                let register = REGISTER_NAMES.iter().position(|name| name == register)?;
+                Some(Breakpoint::Register(register, value.parse().ok()?))
+            },
+            _ => None,
+        }
+    }
+
+    fn is_hit(&self, cpu: &CPU, previous: &[isize]) -> bool {
+        match *self {
+            Breakpoint::Cycle(cycle)                => cpu.current_tick == cycle,
+            Breakpoint::Register(register, value)   => cpu.registers[register] == value && previous[register] != value,
+        }
+    }
+
+    fn describe(&self) -> String {
+        match *self {
+            Breakpoint::Cycle(cycle)                => format!("cycle {}", cycle),
+            Breakpoint::Register(register, value)   => format!("{} == {}", REGISTER_NAMES[register], value),
+        }
+    }
+}
+
+pub struct Debugger<'a> {
+    instruction_set: &'a InstructionSet,
+    program: &'a [Cycle],
+    cpu: CPU,
+    crt: Crt,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(instruction_set: &'a InstructionSet, program: &'a [Cycle]) -> Debugger<'a> {
+        Debugger { instruction_set, program, cpu: CPU::new(), crt: Crt::default(), breakpoints: vec![] }
+    }
+
+    fn tick(&mut self) -> bool {
+        self.cpu.tick(self.instruction_set, self.program, &mut [&mut self.crt])
+    }
+
+    fn hit_breakpoint(&self, previous: &[isize]) -> Option<&Breakpoint> {
+        self.breakpoints.iter().find(|breakpoint| breakpoint.is_hit(&self.cpu, previous))
+    }
+
+    fn status(&self) -> String {
+        if self.cpu.is_halted(self.program) {
+            return format!("program ended after cycle {}", self.cpu.current_tick);
+        }
+
+        let registers = REGISTER_NAMES.iter().zip(self.cpu.registers)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let instruction = match self.program.get(self.cpu.pc) {
+            Some(cycle) => cycle.disassemble(self.instruction_set),
+            None        => String::from("<end>"),
+        };
+
+        let progress = match self.cpu.remaining_ticks {
+            Some(remaining) => format!(", {} cycle(s) left", remaining),
+            None            => String::from(", not started"),
+        };
+
+        format!("cycle {}: {} | pc {}: {}{}",
+            self.cpu.current_tick, registers, self.cpu.pc, instruction, progress)
+    }
+
+    fn listing(&self) -> String {
+        let start = self.cpu.pc.saturating_sub(3);
+
+        self.program.iter().enumerate().skip(start).take(7)
+            .map(|(index, cycle)| format!("{} {:>4}  {}",
+                if index == self.cpu.pc { "=>" } else { "  " }, index, cycle.disassemble(self.instruction_set)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Runs cycles until `done` says to stop, a breakpoint is hit, the program ends
+    /// or `MAX_TICKS` cycles have passed, which stops programs that loop forever.
+    fn run(&mut self, mut done: impl FnMut(&CPU) -> bool) -> String {
+        for _ in 0..MAX_TICKS {
+            let previous = self.cpu.registers;
+            if !self.tick() { return self.status() }
+
+            if let Some(breakpoint) = self.hit_breakpoint(&previous) {
+                return format!("breakpoint: {}\n{}", breakpoint.describe(), self.status());
+            }
+
+            if done(&self.cpu) { return self.status() }
+        }
+
+        format!("stopped after {} cycles, the program may never end\n{}", MAX_TICKS, self.status())
+    }
+
+    /// Executes a single command, returning what to show or `None` to quit.
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        let parts = line.split_whitespace().collect::<Vec<_>>();
+        let count = |default: usize| parts.get(1).and_then(|x| x.parse::<usize>().ok()).unwrap_or(default).max(1);
+
+        Some(match parts.first().copied().unwrap_or("") {
+            "" => String::new(),
+            "step" | "s" => {
+                let mut remaining = count(1);
+                self.run(|_| { remaining -= 1; remaining == 0 })
+            },
+            "next" | "n" => {
+                let mut remaining = count(1);
+                self.run(|cpu| {
+                    if cpu.remaining_ticks == Some(0) { remaining -= 1 }
+                    remaining == 0
+                })
+            },
+            "continue" | "c" => self.run(|_| false),
+            "break" | "b" => match Breakpoint::parse(&parts[1..]) {
+                Some(breakpoint) => {
+                    self.breakpoints.push(breakpoint);
+                    format!("breakpoint {}: {}", self.breakpoints.len() - 1, breakpoint.describe())
+                },
+                None => String::from("usage: break cycle <n> | break <register> <value>"),
+            },
+            "breakpoints" => self.breakpoints.iter().enumerate()
+                .map(|(index, breakpoint)| format!("{}: {}", index, breakpoint.describe()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            "delete" | "d" => match parts.get(1).and_then(|x| x.parse::<usize>().ok()) {
+                Some(index) if index < self.breakpoints.len() => {
+                    format!("deleted {}", self.breakpoints.remove(index).describe())
+                },
+                _ => String::from("no such breakpoint"),
+            },
+            "print" | "p" => self.status(),
+            "crt" => self.crt.render_text(),
+            "list" | "l" => self.listing(),
+            "reset" => {
+                self.cpu = CPU::new();
+                self.crt = Crt::default();
+                self.status()
+            },
+            "help" | "h" => String::from(HELP),
+            "quit" | "q" => return None,
+            command => format!("unknown command '{}', try 'help'", command),
+        })
+    }
+}
+
+/// Reads commands from `input` until it ends or the user quits.
+pub fn repl(debugger: &mut Debugger, input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
+    writeln!(output, "{}", debugger.status())?;
+    write!(output, "(day10) ")?;
+    output.flush()?;
+
+    for line in input.lines() {
+        match debugger.execute(&line?) {
+            Some(response) if response.is_empty() => {},
+            Some(response) => writeln!(output, "{}", response)?,
+            None => return Ok(()),
+        }
+
+        write!(output, "(day10) ")?;
+        output.flush()?;
+    }
+
+    writeln!(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn continue_stops_programs_that_never_end() {
+        let instruction_set = InstructionSet::extended();
+        let program = Cycle::assemble("jmp 0\n", &instruction_set).unwrap();
+        let mut debugger = Debugger::new(&instruction_set, &program);
+
+        let response = debugger.execute("continue").unwrap();
+
+        assert!(response.starts_with(&format!("stopped after {} cycles", MAX_TICKS)), "{}", response);
+        assert_eq!(debugger.cpu.current_tick, MAX_TICKS);
+    }
+}
@@ -1,118 +1,337 @@
-use std::vec;
+mod crt;
+mod debugger;
+mod synth;
 
-#[derive(Debug)]
-enum Cycle {
-    Noop,
-    Addx(isize)
+use std::{env, fs, io, fmt::{self, Display, Formatter}};
+
+use crt::Crt;
+
+const REGISTER_NAMES: [&str; 2] = ["x", "y"];
+
+const X: usize = 0;
+const Y: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    Next,
+    /// Continue at an instruction relative to the current one.
+    Jump(isize),
+}
+
+/// What executing an instruction does once its last cycle has finished.
+type Effect = fn(&mut [isize; REGISTER_NAMES.len()], &[isize]) -> Control;
+
+#[derive(Debug, Clone, Copy)]
+struct Instruction {
+    opcode: &'static str,
+    ticks: usize,
+    operands: usize,
+    effect: Effect,
+}
+
+#[derive(Debug, Clone)]
+struct InstructionSet {
+    instructions: Vec<Instruction>,
+}
+
+impl InstructionSet {
+    /// The instructions the puzzle's handheld device understands.
+    fn standard() -> InstructionSet {
+        InstructionSet { instructions: vec![] }
+            .with(Instruction { opcode: "noop", ticks: 1, operands: 0, effect: |_, _| Control::Next })
+            .with(Instruction { opcode: "addx", ticks: 2, operands: 1, effect: |registers, operands| {
+                registers[X] += operands[0];
+                Control::Next
+            }})
+    }
+
+    /// The standard instructions, plus a second register and relative jumps.
+    fn extended() -> InstructionSet {
+        InstructionSet::standard()
+            .with(Instruction { opcode: "addy", ticks: 2, operands: 1, effect: |registers, operands| {
+                registers[Y] += operands[0];
+                Control::Next
+            }})
+            .with(Instruction { opcode: "jmp", ticks: 1, operands: 1, effect: |_, operands| Control::Jump(operands[0]) })
+    }
+
+    /// Adds an instruction, replacing any existing instruction with the same opcode.
+    fn with(mut self, instruction: Instruction) -> InstructionSet {
+        assert!(instruction.ticks > 0, "'{}' must take at least one cycle", instruction.opcode);
+
+        self.instructions.retain(|x| x.opcode != instruction.opcode);
+        self.instructions.push(instruction);
+        self
+    }
+
+    fn find(&self, opcode: &str) -> Option<usize> {
+        self.instructions.iter().position(|x| x.opcode == opcode)
+    }
+
+    fn get(&self, cycle: &Cycle) -> &Instruction {
+        &self.instructions[cycle.instruction]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cycle {
+    /// Index into the `InstructionSet` the program was read with.
+    instruction: usize,
+    operands: Vec<isize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AssembleErrorKind {
+    UnknownOpcode(String),
+    WrongOperandCount { opcode: String, expected: usize, found: usize },
+    InvalidOperand(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AssembleError {
+    pub line: usize,
+    pub kind: AssembleErrorKind,
+}
+
+impl Display for AssembleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            AssembleErrorKind::UnknownOpcode(opcode)    => write!(f, "unknown opcode '{}'", opcode),
+            AssembleErrorKind::WrongOperandCount { opcode, expected, found } =>
+                write!(f, "'{}' takes {} operand(s), found {}", opcode, expected, found),
+            AssembleErrorKind::InvalidOperand(operand)  => write!(f, "'{}' is not a number", operand),
+        }
+    }
+}
+
+impl From<AssembleError> for io::Error {
+    fn from(error: AssembleError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+    }
 }
 
 impl Cycle {
-    fn collection_from_str(s: &str) -> Vec<Cycle> {
-        s.split_terminator('\n').map(|line| {
-            let parts = line.split(" ").take(2).collect::<Vec<&str>>();
-
-            match *parts.first().expect("Missing operation") {
-                "noop" => return Cycle::Noop,
-                "addx" => {
-                    let value = *parts.get(1).expect("Missing value");
-                    let value = value.parse().unwrap();
-
-                    Cycle::Addx(value)
-                }
-                _ => unreachable!()
+    /// Reads one instruction per line, ignoring blank lines and anything after a `;`.
+    fn assemble(s: &str, instruction_set: &InstructionSet) -> Result<Vec<Cycle>, AssembleError> {
+        s.lines().enumerate().filter_map(|(index, line)| {
+            let line = line.split(';').next().unwrap().trim();
+            if line.is_empty() { return None }
+
+            let error = |kind| AssembleError { line: index + 1, kind };
+            let mut parts = line.split_whitespace();
+
+            let opcode = parts.next().unwrap();
+            let Some(instruction) = instruction_set.find(opcode) else {
+                return Some(Err(error(AssembleErrorKind::UnknownOpcode(String::from(opcode)))));
+            };
+
+            let operands = match parts.map(|value| value.parse().map_err(|_| value)).collect::<Result<Vec<isize>, _>>() {
+                Ok(operands) => operands,
+                Err(value) => return Some(Err(error(AssembleErrorKind::InvalidOperand(String::from(value))))),
+            };
+
+            let expected = instruction_set.instructions[instruction].operands;
+            if operands.len() != expected {
+                return Some(Err(error(AssembleErrorKind::WrongOperandCount {
+                    opcode: String::from(opcode),
+                    expected,
+                    found: operands.len(),
+                })));
             }
-        }).collect::<Vec<_>>()
+
+            Some(Ok(Cycle { instruction, operands }))
+        }).collect()
     }
 
-    fn ticks(&self) -> usize {
-        match self {
-            Cycle::Noop => 1,
-            Cycle::Addx(_) => 2,
-        }
+    /// Writes the instruction back out the way `assemble` reads it.
+    fn disassemble(&self, instruction_set: &InstructionSet) -> String {
+        let opcode = instruction_set.get(self).opcode;
+
+        self.operands.iter().fold(String::from(opcode), |mut line, operand| {
+            line.push_str(&format!(" {}", operand));
+            line
+        })
     }
 }
 
-#[derive(Debug)]
+/// Something that wants to look at the CPU during every cycle.
+trait Observer {
+    fn on_tick(&mut self, cpu: &CPU);
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
 struct CPU {
-    value: isize,
+    registers: [isize; REGISTER_NAMES.len()],
+    /// Index of the instruction being executed.
+    pc: usize,
     current_tick: usize,
+    /// Cycles left of the instruction at `pc`, or `None` when it has not started yet.
+    remaining_ticks: Option<usize>,
 }
 
 impl CPU {
     fn new() -> CPU {
+        let mut registers = [0; REGISTER_NAMES.len()];
+        registers[X] = 1;
+
         CPU {
-            value: 1,
+            registers,
+            pc: 0,
             current_tick: 0,
+            remaining_ticks: None,
         }
     }
 
-    fn draw_pixel(&self) -> () {
-        let lower = (self.value % 40) - 1;
-        let higher = (self.value % 40) + 1;
+    /// Applies the effect of the instruction at `pc` if its last cycle has ended.
+    fn complete(&mut self, instruction_set: &InstructionSet, ticks: &[Cycle]) {
+        if self.remaining_ticks != Some(0) { return }
 
-        let tick = (self.current_tick % 40) as isize;
+        let cycle = &ticks[self.pc];
+        let control = (instruction_set.get(cycle).effect)(&mut self.registers, &cycle.operands);
 
-        if tick >= lower && tick <= higher {
-            print!("#");
-        } else {
-            print!(".");
-        }
+        self.remaining_ticks = None;
+        self.pc = match control {
+            Control::Next           => self.pc + 1,
+            Control::Jump(offset)   => self.pc.checked_add_signed(offset).unwrap_or(usize::MAX),
+        };
+    }
 
-        if tick % 40 == 39 {
-            println!("");
-        }
+    /// Starts the next cycle and calls every observer during it, returning `false` once the program has ended.
+    /// An instruction only takes effect when the cycle after its last one starts, so in between calls the
+    /// registers always hold their value during cycle `current_tick`.
+    fn tick(&mut self, instruction_set: &InstructionSet, ticks: &[Cycle], observers: &mut [&mut dyn Observer]) -> bool {
+        self.complete(instruction_set, ticks);
+
+        let remaining = match self.remaining_ticks {
+            Some(remaining) => remaining,
+            None => match ticks.get(self.pc) {
+                Some(cycle) => instruction_set.get(cycle).ticks,
+                None        => return false,
+            },
+        };
+
+        self.current_tick += 1;
+        self.remaining_ticks = Some(remaining - 1);
+
+        observers.iter_mut().for_each(|observer| observer.on_tick(self));
+
+        true
     }
 
-    fn measure_signal_strength(&self) -> Option<isize> {
-        let is_20 = self.current_tick == 20;
-        let is_40_after_20 = (self.current_tick as isize - 20) % 40 == 0;
+    fn is_halted(&self, ticks: &[Cycle]) -> bool {
+        self.remaining_ticks.is_none() && self.pc >= ticks.len()
+    }
 
-        if is_20 || is_40_after_20 {
-            Some(self.current_tick as isize * self.value)
-        } else {
-            None
+    /// Runs the program until it jumps or falls outside of it, or `max_ticks` cycles have passed.
+    fn process_ticks(&mut self, instruction_set: &InstructionSet, ticks: &[Cycle], max_ticks: Option<usize>, observers: &mut [&mut dyn Observer]) {
+        while max_ticks.is_none_or(|max_ticks| self.current_tick < max_ticks) {
+            if !self.tick(instruction_set, ticks, observers) { break }
         }
-    }
 
-    fn process_tick(&mut self, cycle: &Cycle) -> Vec<isize> {
-        let mut sum = vec![];
+        self.complete(instruction_set, ticks);
+    }
+}
 
-        for _ in 0..cycle.ticks() {
-            self.draw_pixel();
+/// Sums the signal strength during the 20th cycle and every 40 cycles after that.
+#[derive(Debug, Default)]
+struct SignalStrength {
+    sum: isize,
+}
 
-            self.current_tick += 1;
-            if let Some(value) = self.measure_signal_strength() {
-                sum.push(value);
-            }
+impl Observer for SignalStrength {
+    fn on_tick(&mut self, cpu: &CPU) {
+        if cpu.current_tick % 40 == 20 {
+            self.sum += cpu.current_tick as isize * cpu.registers[X];
         }
+    }
+}
 
-        if let Cycle::Addx(value) = cycle {
-            self.value += value;
-        }
+fn part1(instruction_set: &InstructionSet, cycles: &[Cycle]) -> isize {
+    let mut signal_strength = SignalStrength::default();
+    CPU::new().process_ticks(instruction_set, cycles, None, &mut [&mut signal_strength]);
 
-        sum
-    }
+    signal_strength.sum
+}
 
-    fn process_ticks(&mut self, ticks: &Vec<Cycle>) -> Vec<isize> {
-        let mut measurements = Vec::<isize>::new();
+fn draw(instruction_set: &InstructionSet, cycles: &[Cycle]) -> Crt {
+    let mut crt = Crt::default();
+    CPU::new().process_ticks(instruction_set, cycles, None, &mut [&mut crt]);
 
-        ticks.iter().for_each(|cycle| {
-            measurements.append(&mut self.process_tick(cycle));
-        });
+    crt
+}
 
-        measurements
-    }
+fn part2(instruction_set: &InstructionSet, cycles: &[Cycle]) -> String {
+    draw(instruction_set, cycles).read_letters()
 }
 
-fn solve(input: &str) -> () {
-    let cycles = Cycle::collection_from_str(input);
+const MAX_TICKS: usize = 1_000_000;
+const IMAGE_SCALE: usize = 8;
+
+/// Runs a program with the extended instruction set and prints the registers it ends with.
+fn run(path: &str) -> io::Result<()> {
+    let instruction_set = InstructionSet::extended();
+    let cycles = Cycle::assemble(&fs::read_to_string(path)?, &instruction_set)?;
     let mut cpu = CPU::new();
 
-    let signal_strength: isize = cpu.process_ticks(&cycles).iter().sum();
-    println!("signal strength: {}", signal_strength);
+    cpu.process_ticks(&instruction_set, &cycles, Some(MAX_TICKS), &mut []);
+
+    println!("ticks: {}", cpu.current_tick);
+    REGISTER_NAMES.iter().zip(cpu.registers).for_each(|(name, value)| println!("{}: {}", name, value));
+
+    Ok(())
+}
+
+fn debug(instruction_set: &InstructionSet, cycles: &[Cycle]) -> io::Result<()> {
+    let mut debugger = debugger::Debugger::new(instruction_set, cycles);
+    debugger::repl(&mut debugger, io::stdin().lock(), &mut io::stdout().lock())
+}
+
+/// Prints a program that draws `target`, after checking that it does.
+fn synthesise(target: Option<Crt>) -> io::Result<()> {
+    let target = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot read the target image"))?;
+    let instruction_set = InstructionSet::standard();
+
+    let program = synth::synthesise(&target, &instruction_set)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))?;
+
+    program.iter().for_each(|cycle| println!("{}", cycle.disassemble(&instruction_set)));
+
+    Ok(())
 }
 
 fn main() {
     let input = include_str!("input.txt");
-    solve(input);
+    let instruction_set = InstructionSet::standard();
+    let cycles = Cycle::assemble(input, &instruction_set).expect("Invalid puzzle input");
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["run", path]   => Some(run(path)),
+        ["debug"]       => Some(debug(&instruction_set, &cycles)),
+        ["debug", path] => Some(fs::read_to_string(path).and_then(|program| {
+            let instruction_set = InstructionSet::extended();
+            debug(&instruction_set, &Cycle::assemble(&program, &instruction_set)?)
+        })),
+        ["synth", path]         => Some(fs::read_to_string(path).and_then(|image| synthesise(Crt::from_text(&image)))),
+        ["synth-text", text]    => Some(synthesise(Crt::from_letters(text))),
+        ["image", path] => Some(fs::write(path, draw(&instruction_set, &cycles).render_pbm(IMAGE_SCALE))),
+        _               => None,
+    };
+
+    if let Some(result) = command {
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    println!("part1: {}", part1(&instruction_set, &cycles));
+    println!("part2: {}", part2(&instruction_set, &cycles));
+    println!("{}", draw(&instruction_set, &cycles).render_text());
 }
@@ -0,0 +1,142 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{crt::Crt, Cycle, InstructionSet, CPU};
+
+/// X values worth considering: one past either side of the screen is enough to keep every pixel dark.
+const MIN_VALUE: isize = -2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SynthesisError {
+    MissingInstruction(&'static str),
+    /// No sequence of X values can light exactly these pixels; the first pixel that cannot be reached.
+    Unreachable(usize),
+    /// The program ran but drew something else, which would be a bug in the synthesiser.
+    Mismatch,
+}
+
+impl Display for SynthesisError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SynthesisError::MissingInstruction(opcode)  => write!(f, "the instruction set has no '{}'", opcode),
+            SynthesisError::Unreachable(pixel)          => write!(f, "no program can draw pixel {} as required", pixel),
+            SynthesisError::Mismatch                    => write!(f, "the program did not draw the image"),
+        }
+    }
+}
+
+/// Finds the value of X during every cycle, one per pixel, such that exactly the lit pixels are drawn.
+///
+/// X only changes when an `addx` finishes, so apart from the last one every run of equal values must
+/// last at least the two cycles of the `addx` that ends it. The search keeps, for every cycle, which
+/// `(value, run at least two cycles long)` states can be reached and where they came from.
+fn schedule(target: &Crt) -> Result<Vec<isize>, SynthesisError> {
+    let max_value = target.width as isize + 1;
+    let values = (max_value - MIN_VALUE + 1) as usize;
+    let state = |value: isize, settled: bool| (value - MIN_VALUE) as usize * 2 + settled as usize;
+
+    let allowed = |pixel: usize, value: isize| {
+        let column = (pixel % target.width) as isize;
+        ((column - value).abs() <= 1) == target.pixels[pixel]
+    };
+
+    // For every pixel and state, the state during the previous pixel.
+    let mut previous: Vec<Vec<Option<usize>>> = vec![vec![None; values * 2]];
+    let mut reachable = vec![false; values * 2];
+
+    if !allowed(0, 1) { return Err(SynthesisError::Unreachable(0)) }
+    reachable[state(1, false)] = true;
+
+    for pixel in 1..target.pixels.len() {
+        let mut layer = vec![None; values * 2];
+
+        for value in MIN_VALUE..=max_value {
+            if !allowed(pixel, value) { continue }
+
+            let stay = [state(value, true), state(value, false)].into_iter().find(|&x| reachable[x]);
+            let change = (MIN_VALUE..=max_value)
+                .map(|from| state(from, true))
+                .find(|&x| reachable[x] && x != state(value, true));
+
+            layer[state(value, true)] = stay;
+            layer[state(value, false)] = change;
+        }
+
+        reachable = layer.iter().map(Option::is_some).collect();
+        if !reachable.contains(&true) { return Err(SynthesisError::Unreachable(pixel)) }
+
+        previous.push(layer);
+    }
+
+    let mut current = (0..values * 2).find(|&x| reachable[x]).unwrap();
+    let mut result = vec![0; target.pixels.len()];
+
+    for pixel in (0..target.pixels.len()).rev() {
+        result[pixel] = (current / 2) as isize + MIN_VALUE;
+        if pixel > 0 { current = previous[pixel][current].unwrap() }
+    }
+
+    Ok(result)
+}
+
+/// Builds a program whose run on `CPU` draws exactly `target`, then checks that it does.
+pub fn synthesise(target: &Crt, instruction_set: &InstructionSet) -> Result<Vec<Cycle>, SynthesisError> {
+    let noop = instruction_set.find("noop").ok_or(SynthesisError::MissingInstruction("noop"))?;
+    let addx = instruction_set.find("addx").ok_or(SynthesisError::MissingInstruction("addx"))?;
+
+    let values = schedule(target)?;
+    let mut program = vec![];
+    let mut start = 0;
+
+    while start < values.len() {
+        let length = values[start..].iter().take_while(|&&x| x == values[start]).count();
+        let end = start + length;
+
+        let noops = if end < values.len() { length - 2 } else { length };
+        program.extend((0..noops).map(|_| Cycle { instruction: noop, operands: vec![] }));
+
+        if end < values.len() {
+            program.push(Cycle { instruction: addx, operands: vec![values[end] - values[start]] });
+        }
+
+        start = end;
+    }
+
+    let mut crt = Crt::new(target.width, target.height);
+    CPU::new().process_ticks(instruction_set, &program, None, &mut [&mut crt]);
+
+    if crt != *target { return Err(SynthesisError::Mismatch) }
+
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::draw;
+
+    #[test]
+    fn letters_survive_synthesis_and_ocr() {
+        let instruction_set = InstructionSet::standard();
+
+        for text in ["ZHEB", "ZGCJZJFL", "EFGHIJKL", "BOPRSUYC", "RACE", "PL"] {
+            let program = synthesise(&Crt::from_letters(text).unwrap(), &instruction_set).unwrap();
+
+            assert_eq!(draw(&instruction_set, &program).read_letters(), text);
+        }
+    }
+
+    #[test]
+    fn first_two_pixels_must_be_lit() {
+        let instruction_set = InstructionSet::standard();
+
+        // X starts at 1 and cannot change before the end of the second cycle, so the sprite covers both pixels.
+        for (pixel, error) in [(0, SynthesisError::Unreachable(0)), (1, SynthesisError::Unreachable(1))] {
+            let mut target = Crt::from_letters("ZHEB").unwrap();
+            target.pixels[pixel] = false;
+
+            assert_eq!(synthesise(&target, &instruction_set), Err(error));
+        }
+
+        assert_eq!(synthesise(&Crt::default(), &instruction_set), Err(SynthesisError::Unreachable(0)));
+    }
+}
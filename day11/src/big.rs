@@ -0,0 +1,264 @@
+use std::{cmp::Ordering, fmt::{self, Display, Formatter}};
+
+/// An arbitrary-precision unsigned integer, as base 2^32 digits with the least significant first
+/// and no leading zero digits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    digits: Vec<u32>,
+}
+
+impl From<usize> for BigUint {
+    fn from(value: usize) -> BigUint {
+        let value = value as u64;
+        BigUint::normalised(vec![value as u32, (value >> 32) as u32])
+    }
+}
+
+impl BigUint {
+    fn normalised(mut digits: Vec<u32>) -> BigUint {
+        while digits.last() == Some(&0) { digits.pop(); }
+        BigUint { digits }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn bits(&self) -> usize {
+        match self.digits.last() {
+            Some(last) => self.digits.len() * 32 - last.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    pub fn to_usize(&self) -> Option<usize> {
+        if self.bits() > usize::BITS as usize { return None }
+
+        Some(self.digits.iter().rev().fold(0u128, |acc, &digit| (acc << 32) | digit as u128) as usize)
+    }
+
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let (long, short) = if self.digits.len() >= other.digits.len() { (self, other) } else { (other, self) };
+        let mut digits = Vec::with_capacity(long.digits.len() + 1);
+        let mut carry = 0u64;
+
+        for (index, &digit) in long.digits.iter().enumerate() {
+            let sum = digit as u64 + *short.digits.get(index).unwrap_or(&0) as u64 + carry;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+
+        digits.push(carry as u32);
+        BigUint::normalised(digits)
+    }
+
+    /// `self - other`, or `None` when that would be negative.
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if self.cmp(other) == Ordering::Less { return None }
+
+        let mut digits = Vec::with_capacity(self.digits.len());
+        let mut borrow = 0i64;
+
+        for (index, &digit) in self.digits.iter().enumerate() {
+            let mut difference = digit as i64 - *other.digits.get(index).unwrap_or(&0) as i64 - borrow;
+            borrow = (difference < 0) as i64;
+            if difference < 0 { difference += 1 << 32 }
+            digits.push(difference as u32);
+        }
+
+        Some(BigUint::normalised(digits))
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() { return BigUint { digits: vec![] } }
+
+        let mut digits = vec![0u32; self.digits.len() + other.digits.len()];
+
+        for (i, &a) in self.digits.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, &b) in other.digits.iter().enumerate() {
+                let product = a as u64 * b as u64 + digits[i + j] as u64 + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+
+            digits[i + other.digits.len()] = carry as u32;
+        }
+
+        BigUint::normalised(digits)
+    }
+
+    /// Divides by a single digit, returning the quotient and remainder.
+    fn div_rem_digit(&self, divisor: u32) -> (BigUint, u32) {
+        let mut digits = vec![0; self.digits.len()];
+        let mut remainder = 0u64;
+
+        for (index, &digit) in self.digits.iter().enumerate().rev() {
+            let current = (remainder << 32) | digit as u64;
+            digits[index] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+
+        (BigUint::normalised(digits), remainder as u32)
+    }
+
+    /// The quotient and remainder of `self / divisor`, or `None` when dividing by zero.
+    ///
+    /// Division by more than one digit is done a bit at a time, which is slow but rarely needed.
+    pub fn div_rem(&self, divisor: &BigUint) -> Option<(BigUint, BigUint)> {
+        match divisor.digits.as_slice() {
+            [] => return None,
+            [digit] => {
+                let (quotient, remainder) = self.div_rem_digit(*digit);
+                return Some((quotient, BigUint::from(remainder as usize)));
+            },
+            _ => {},
+        }
+
+        let mut quotient = vec![0u32; self.digits.len()];
+        let mut remainder = BigUint { digits: vec![] };
+
+        for bit in (0..self.bits()).rev() {
+            remainder = remainder.add(&remainder);
+            if (self.digits[bit / 32] >> (bit % 32)) & 1 == 1 { remainder = remainder.add(&BigUint::from(1)) }
+
+            if let Some(difference) = remainder.checked_sub(divisor) {
+                remainder = difference;
+                quotient[bit / 32] |= 1 << (bit % 32);
+            }
+        }
+
+        Some((BigUint::normalised(quotient), remainder))
+    }
+
+    /// `self % modulus` for a modulus that fits a machine word, which is all a divisibility test needs.
+    pub fn rem_usize(&self, modulus: usize) -> usize {
+        self.digits.iter().rev().fold(0u128, |acc, &digit| ((acc << 32) | digit as u128) % modulus as u128) as usize
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.digits.len().cmp(&other.digits.len())
+            .then_with(|| self.digits.iter().rev().cmp(other.digits.iter().rev()))
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+
+        if self.is_zero() { return write!(f, "0") }
+
+        let mut chunks = vec![];
+        let mut rest = self.clone();
+
+        while !rest.is_zero() {
+            let (quotient, remainder) = rest.div_rem_digit(CHUNK);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+        chunks.iter().rev().try_for_each(|chunk| write!(f, "{:09}", chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(value: u128) -> BigUint {
+        BigUint::normalised((0..4).map(|index| (value >> (32 * index)) as u32).collect())
+    }
+
+    fn to_u128(value: &BigUint) -> u128 {
+        assert!(value.digits.len() <= 4 && value.digits.last() != Some(&0), "{:?} is not normalised", value);
+        value.digits.iter().rev().fold(0, |acc, &digit| (acc << 32) | digit as u128)
+    }
+
+    /// Random operands of every length up to `bits`, with runs of set and clear bits to force carries and borrows.
+    fn operands(bits: u32, count: usize) -> Vec<u128> {
+        let mut state = 0x853c_49e6_748f_ea9b_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut values = vec![0, 1, u32::MAX as u128, 1 << 32, u64::MAX as u128, 1 << 64];
+        values.extend((0..count).map(|index| {
+            let value = ((next() as u128) << 64 | next() as u128) >> (128 - bits);
+            let value = value >> (next() % bits as u64);
+
+            match index % 3 {
+                0 => value,
+                1 => value | 0xFFFF_FFFF_0000_0000,
+                _ => value & !0xFFFF_FFFF,
+            }
+        }));
+
+        values.retain(|&x| x >> bits == 0);
+        values
+    }
+
+    #[test]
+    fn arithmetic_matches_u128() {
+        let values = operands(127, 60);
+
+        for &a in values.iter() {
+            for &b in values.iter() {
+                assert_eq!(to_u128(&big(a).add(&big(b))), a + b, "{} + {}", a, b);
+                assert_eq!(big(a).checked_sub(&big(b)).map(|x| to_u128(&x)), a.checked_sub(b), "{} - {}", a, b);
+                assert_eq!(big(a).cmp(&big(b)), a.cmp(&b), "{} <=> {}", a, b);
+
+                let expected = (b != 0).then(|| (a / b, a % b));
+                let actual = big(a).div_rem(&big(b)).map(|(quotient, remainder)| (to_u128(&quotient), to_u128(&remainder)));
+                assert_eq!(actual, expected, "{} / {}", a, b);
+            }
+
+            assert_eq!(big(a).to_string(), a.to_string());
+            assert_eq!(big(a).bits(), 128 - a.leading_zeros() as usize);
+            assert_eq!(big(a).to_usize(), usize::try_from(a).ok());
+        }
+    }
+
+    #[test]
+    fn multiplication_matches_u128() {
+        let values = operands(64, 60);
+
+        for &a in values.iter() {
+            for &b in values.iter() {
+                assert_eq!(to_u128(&big(a).mul(&big(b))), a * b, "{} * {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn remainder_matches_u128() {
+        for &a in operands(127, 60).iter() {
+            for modulus in [1, 2, 3, 96577, 9_699_690, u32::MAX as usize, usize::MAX] {
+                assert_eq!(big(a).rem_usize(modulus) as u128, a % modulus as u128, "{} % {}", a, modulus);
+            }
+        }
+    }
+
+    #[test]
+    fn multi_digit_division_beyond_u128() {
+        let a = big(u128::MAX).mul(&big(u128::MAX)).add(&big(12345));
+        let divisor = big((1 << 70) + 3);
+
+        let (quotient, remainder) = a.div_rem(&divisor).unwrap();
+
+        assert!(remainder < divisor);
+        assert_eq!(quotient.mul(&divisor).add(&remainder), a);
+    }
+}
@@ -0,0 +1,138 @@
+use super::{rules::Relief, MonkeyCollective};
+
+/// Where an item is at the start of a round: the monkey holding it and its worry level modulo `divisor`.
+type ItemState = (usize, usize);
+
+impl MonkeyCollective {
+    /// Moves a single item through one round, calling `inspect` for every monkey that inspects it.
+    ///
+    /// Items never affect each other, so an item can be followed on its own: monkeys take their turns
+    /// in order, so one thrown to a later monkey is inspected again in the same round, while one
+    /// thrown to an earlier monkey waits for the next round.
+    ///
+    /// Every operation must have passed `check_reducible`, which is what `inspections_after` starts with.
+    fn item_round(&self, (mut monkey, mut worry): ItemState, inspect: &mut impl FnMut(usize)) -> ItemState {
+        loop {
+            inspect(monkey);
+
+            let (target, next_worry) = self.monkeys[monkey].throw(worry, Relief::Reduce, self.divisor)
+                .expect("Operations are checked to be reducible");
+            let thrown_forward = target > monkey;
+
+            (monkey, worry) = (target, next_worry);
+            if !thrown_forward { return (monkey, worry) }
+        }
+    }
+
+    fn item_rounds(&self, mut state: ItemState, rounds: u64, inspect: &mut impl FnMut(usize)) -> ItemState {
+        for _ in 0..rounds {
+            state = self.item_round(state, inspect);
+        }
+
+        state
+    }
+
+    /// Finds the rounds before an item's state starts repeating, and the length of the cycle after that,
+    /// using Brent's algorithm so that nothing has to be remembered along the way.
+    fn item_cycle(&self, start: ItemState) -> (u64, u64) {
+        let mut power = 1;
+        let mut length = 1;
+        let mut tortoise = start;
+        let mut hare = self.item_round(start, &mut |_| {});
+
+        while tortoise != hare {
+            if power == length {
+                tortoise = hare;
+                power *= 2;
+                length = 0;
+            }
+
+            hare = self.item_round(hare, &mut |_| {});
+            length += 1;
+        }
+
+        let mut tortoise = start;
+        let mut hare = self.item_rounds(start, length, &mut |_| {});
+        let mut prefix = 0;
+
+        while tortoise != hare {
+            tortoise = self.item_round(tortoise, &mut |_| {});
+            hare = self.item_round(hare, &mut |_| {});
+            prefix += 1;
+        }
+
+        (prefix, length)
+    }
+
+    /// Counts how many items every monkey inspects in `rounds` rounds with the part 2 rules, without
+    /// simulating them all: every item's inspections are extrapolated once its state starts repeating.
+    /// Fails if some operation cannot be worked out modulo `divisor`.
+    pub fn inspections_after(&self, rounds: u64) -> Result<Vec<u64>, String> {
+        self.check_reducible()?;
+
+        let mut inspected = vec![0; self.monkeys.len()];
+
+        for (monkey, items) in self.monkeys.iter().enumerate().map(|(index, monkey)| (index, &monkey.items)) {
+            for &worry in items {
+                let start = (monkey, worry % self.divisor);
+                let (prefix, length) = self.item_cycle(start);
+
+                if rounds <= prefix + length {
+                    self.item_rounds(start, rounds, &mut |monkey| inspected[monkey] += 1);
+                    continue;
+                }
+
+                let mut cycle = vec![0; self.monkeys.len()];
+                let state = self.item_rounds(start, prefix, &mut |monkey| inspected[monkey] += 1);
+                let state = self.item_rounds(state, length, &mut |monkey| cycle[monkey] += 1);
+
+                let (repeats, remainder) = ((rounds - prefix) / length, (rounds - prefix) % length);
+                self.item_rounds(state, remainder, &mut |monkey| inspected[monkey] += 1);
+
+                for (total, count) in inspected.iter_mut().zip(cycle) {
+                    *total += repeats * count;
+                }
+            }
+        }
+
+        Ok(inspected)
+    }
+}
+
+/// The product of the two highest inspection counts, which no longer fits a `u64` after enough rounds.
+pub fn monkey_business(inspected: &[u64]) -> u128 {
+    let mut inspected = inspected.to_vec();
+    inspected.sort_unstable_by_key(|&x| std::cmp::Reverse(x));
+
+    inspected.iter().take(2).map(|&x| x as u128).product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{rules::Rules, tests::EXAMPLE};
+
+    #[test]
+    fn fast_forward_matches_playing_every_round() {
+        let monkey_collective = MonkeyCollective::parse(EXAMPLE).unwrap();
+
+        for rounds in [1, 20, 1000, 10000] {
+            let mut played = monkey_collective.clone();
+            (0..rounds).for_each(|_| played.round(Relief::Reduce, None).unwrap());
+
+            let expected = played.monkeys.iter().map(|monkey| monkey.inspected as u64).collect::<Vec<_>>();
+            assert_eq!(monkey_collective.inspections_after(rounds), Ok(expected), "after {} rounds", rounds);
+        }
+
+        let inspected = monkey_collective.inspections_after(10000).unwrap();
+        assert_eq!(monkey_business(&inspected), 2713310158);
+        assert_eq!(monkey_business(&inspected), monkey_collective.clone().run(&Rules::part2()).unwrap().unwrap() as u128);
+    }
+
+    #[test]
+    fn operations_that_cannot_be_reduced_are_refused() {
+        let monkey_collective = MonkeyCollective::parse(&EXAMPLE.replace("old * old", "old / 2")).unwrap();
+
+        assert_eq!(monkey_collective.inspections_after(10), Err(format!("'old / 2' cannot be reduced modulo {}", 96577)));
+    }
+}
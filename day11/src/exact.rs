@@ -0,0 +1,114 @@
+use super::{big::BigUint, rules::Relief, MonkeyCollective};
+
+/// A throw where an item's worry level stops fitting a `usize`, which is where a simulation without
+/// any reduction would have overflowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    pub round: usize,
+    pub from: usize,
+    pub to: usize,
+    /// The size of the worry level before and after the inspection.
+    pub bits_before: usize,
+    pub bits_after: usize,
+}
+
+pub struct ExactRun {
+    pub inspected: Vec<usize>,
+    /// The items every monkey holds at the end, in the same order as `MonkeyCollective::round` keeps them.
+    pub items: Vec<Vec<BigUint>>,
+    pub overflows: Vec<Overflow>,
+}
+
+impl MonkeyCollective {
+    /// Runs `rounds` rounds with the part 2 rules, but keeps every worry level exactly instead of reducing
+    /// it modulo `divisor`. Worry levels can double in length every round, so only a few rounds are practical.
+    /// Fails if an operation goes below zero or divides by zero.
+    pub fn run_exact(&self, rounds: usize) -> Result<ExactRun, String> {
+        let mut items = self.monkeys.iter()
+            .map(|monkey| monkey.items.iter().map(|&x| BigUint::from(x)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let mut inspected = vec![0; self.monkeys.len()];
+        let mut overflows = vec![];
+
+        for round in 1..=rounds {
+            for (index, monkey) in self.monkeys.iter().enumerate() {
+                while let Some(worry_level) = items[index].pop() {
+                    let next = monkey.operation.evaluate_big(&worry_level).ok_or_else(|| {
+                        format!("round {}, monkey {}: new = {} is out of range for old = {}", round, index, monkey.operation, worry_level)
+                    })?;
+
+                    let target = if next.rem_usize(monkey.test.value) == 0 { monkey.test.if_true } else { monkey.test.if_false };
+
+                    if worry_level.to_usize().is_some() && next.to_usize().is_none() {
+                        overflows.push(Overflow { round, from: index, to: target, bits_before: worry_level.bits(), bits_after: next.bits() });
+                    }
+
+                    inspected[index] += 1;
+                    items[target].push(next);
+                }
+            }
+        }
+
+        Ok(ExactRun { inspected, items, overflows })
+    }
+
+    /// Checks that reducing worry levels modulo `divisor` changes nothing for `rounds` rounds: every monkey
+    /// inspects as many items, and holds the same items reduced, as without any reduction.
+    pub fn verify_reduction(&self, rounds: usize) -> Result<ExactRun, String> {
+        let exact = self.run_exact(rounds)?;
+        let mut reduced = self.clone();
+
+        for _ in 0..rounds {
+            reduced.round(Relief::Reduce, None)?;
+        }
+
+        for (index, monkey) in reduced.monkeys.iter().enumerate() {
+            if monkey.inspected != exact.inspected[index] {
+                return Err(format!("monkey {} inspected {} items, but {} without reduction",
+                    index, monkey.inspected, exact.inspected[index]));
+            }
+
+            let expected = exact.items[index].iter().map(|x| x.rem_usize(self.divisor)).collect::<Vec<_>>();
+            if monkey.items != expected {
+                return Err(format!("monkey {} holds {:?}, but {:?} without reduction", index, monkey.items, expected));
+            }
+        }
+
+        Ok(exact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::EXAMPLE;
+
+    #[test]
+    fn reduction_changes_nothing_on_the_example() {
+        let monkey_collective = MonkeyCollective::parse(EXAMPLE).unwrap();
+        let run = monkey_collective.verify_reduction(20).unwrap();
+
+        let mut reduced = monkey_collective.clone();
+        (0..20).for_each(|_| reduced.round(Relief::Reduce, None).unwrap());
+
+        assert_eq!(run.inspected, reduced.monkeys.iter().map(|monkey| monkey.inspected).collect::<Vec<_>>());
+        assert!(run.overflows.iter().all(|overflow| overflow.bits_before <= 64 && overflow.bits_after > 64));
+        assert_eq!(run.overflows.len(), 5);
+    }
+
+    #[test]
+    fn wrong_divisor_is_caught() {
+        let mut monkey_collective = MonkeyCollective::parse(EXAMPLE).unwrap();
+        monkey_collective.divisor = 23 * 19 * 13;
+
+        assert!(monkey_collective.verify_reduction(20).is_err());
+    }
+
+    #[test]
+    fn negative_worry_levels_are_reported() {
+        let monkey_collective = MonkeyCollective::parse(&EXAMPLE.replace("old + 6", "old - 60")).unwrap();
+
+        assert_eq!(monkey_collective.run_exact(1).err(), Some(String::from("round 1, monkey 1: new = old - 60 is out of range for old = 54")));
+    }
+}
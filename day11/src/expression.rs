@@ -0,0 +1,316 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::big::BigUint;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+}
+
+impl Operator {
+    fn from_byte(byte: u8) -> Option<Operator> {
+        Some(match byte {
+            b'+' => Operator::Add,
+            b'-' => Operator::Subtract,
+            b'*' => Operator::Multiply,
+            b'/' => Operator::Divide,
+            b'%' => Operator::Remainder,
+            _    => return None,
+        })
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Operator::Add | Operator::Subtract => 1,
+            Operator::Multiply | Operator::Divide | Operator::Remainder => 2,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            Operator::Add       => '+',
+            Operator::Subtract  => '-',
+            Operator::Multiply  => '*',
+            Operator::Divide    => '/',
+            Operator::Remainder => '%',
+        }
+    }
+}
+
+/// How a monkey changes the worry level of an item, e.g. `old * (old + 3) % 7`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Old,
+    Number(usize),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        Err(ParseError { position: self.position, message: String::from(message) })
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.position).is_some_and(u8::is_ascii_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.input.get(self.position).copied()
+    }
+
+    /// Parses operators of at least `min_precedence`, left to right.
+    fn expression(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let mut lhs = self.operand()?;
+
+        while let Some(operator) = self.peek().and_then(Operator::from_byte) {
+            if operator.precedence() < min_precedence { break }
+
+            self.position += 1;
+            let rhs = self.expression(operator.precedence() + 1)?;
+            lhs = Expression::Binary(Box::new(lhs), operator, Box::new(rhs));
+        }
+
+        Ok(lhs)
+    }
+
+    fn operand(&mut self) -> Result<Expression, ParseError> {
+        match self.peek() {
+            Some(b'(') => {
+                self.position += 1;
+                let expression = self.expression(0)?;
+
+                if self.peek() != Some(b')') { return self.error("expected ')'") }
+                self.position += 1;
+
+                Ok(expression)
+            },
+            Some(byte) if byte.is_ascii_digit() => {
+                let start = self.position;
+                while self.input.get(self.position).is_some_and(u8::is_ascii_digit) {
+                    self.position += 1;
+                }
+
+                let digits = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+                match digits.parse() {
+                    Ok(value) => Ok(Expression::Number(value)),
+                    Err(_) => { self.position = start; self.error("number too large") },
+                }
+            },
+            Some(_) if self.input[self.position..].starts_with(b"old") => {
+                self.position += 3;
+                Ok(Expression::Old)
+            },
+            Some(_) => self.error("expected a number, 'old' or '('"),
+            None    => self.error("unexpected end of expression"),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(s: &str) -> Result<Expression, ParseError> {
+        let mut parser = Parser { input: s.as_bytes(), position: 0 };
+        let expression = parser.expression(0)?;
+
+        if parser.peek().is_some() { return parser.error("unexpected character") }
+
+        Ok(expression)
+    }
+
+    /// Evaluates the expression for the worry level `old`. Worry levels are never negative, so like
+    /// `evaluate_big`, `None` is returned as soon as a subtraction goes below zero, as well as on overflow
+    /// or division by zero.
+    pub fn evaluate(&self, old: usize) -> Option<usize> {
+        match self {
+            Expression::Old         => Some(old),
+            Expression::Number(x)   => Some(*x),
+            Expression::Binary(lhs, operator, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate(old)?, rhs.evaluate(old)?);
+
+                match operator {
+                    Operator::Add       => lhs.checked_add(rhs),
+                    Operator::Subtract  => lhs.checked_sub(rhs),
+                    Operator::Multiply  => lhs.checked_mul(rhs),
+                    Operator::Divide    => lhs.checked_div(rhs),
+                    Operator::Remainder => lhs.checked_rem(rhs),
+                }
+            }
+        }
+    }
+
+    /// Evaluates the expression exactly, however large the result. Unlike `evaluate` it cannot overflow,
+    /// so `None` is only returned when a subtraction goes below zero or on division by zero.
+    pub fn evaluate_big(&self, old: &BigUint) -> Option<BigUint> {
+        match self {
+            Expression::Old         => Some(old.clone()),
+            Expression::Number(x)   => Some(BigUint::from(*x)),
+            Expression::Binary(lhs, operator, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate_big(old)?, rhs.evaluate_big(old)?);
+
+                match operator {
+                    Operator::Add       => Some(lhs.add(&rhs)),
+                    Operator::Subtract  => lhs.checked_sub(&rhs),
+                    Operator::Multiply  => Some(lhs.mul(&rhs)),
+                    Operator::Divide    => lhs.div_rem(&rhs).map(|(quotient, _)| quotient),
+                    Operator::Remainder => lhs.div_rem(&rhs).map(|(_, remainder)| remainder),
+                }
+            }
+        }
+    }
+
+    /// Evaluates the expression modulo `modulus`, so that `evaluate_modulo(old % m, m) == evaluate(old) % m`.
+    /// Division cannot be done this way, and a remainder only when it is by a constant dividing `modulus`,
+    /// so those return `None`.
+    pub fn evaluate_modulo(&self, old: usize, modulus: usize) -> Option<usize> {
+        let m = modulus as u128;
+
+        match self {
+            Expression::Old         => Some(old % modulus),
+            Expression::Number(x)   => Some(x % modulus),
+            Expression::Binary(lhs, Operator::Remainder, rhs) => match **rhs {
+                Expression::Number(divisor) if divisor != 0 && modulus.is_multiple_of(divisor) => {
+                    Some(lhs.evaluate_modulo(old, modulus)? % divisor)
+                },
+                _ => None,
+            },
+            Expression::Binary(lhs, operator, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate_modulo(old, modulus)? as u128, rhs.evaluate_modulo(old, modulus)? as u128);
+
+                let result = match operator {
+                    Operator::Add       => (lhs + rhs) % m,
+                    Operator::Subtract  => (lhs + m - rhs) % m,
+                    Operator::Multiply  => (lhs * rhs) % m,
+                    Operator::Divide | Operator::Remainder => return None,
+                };
+
+                Some(result as usize)
+            }
+        }
+    }
+
+    /// Whether `evaluate_modulo` can be used with `modulus`. Reduced worry levels say nothing about whether
+    /// a subtraction would have gone below zero, so every subtraction must be known not to for any `old`.
+    pub fn supports_modulo(&self, modulus: usize) -> bool {
+        self.min_value().is_some() && self.is_reducible(modulus)
+    }
+
+    fn is_reducible(&self, modulus: usize) -> bool {
+        match self {
+            Expression::Old | Expression::Number(_) => true,
+            Expression::Binary(_, Operator::Divide, _) => false,
+            Expression::Binary(lhs, Operator::Remainder, rhs) => {
+                matches!(**rhs, Expression::Number(divisor) if divisor != 0 && modulus.is_multiple_of(divisor)) && lhs.is_reducible(modulus)
+            },
+            Expression::Binary(lhs, _, rhs) => lhs.is_reducible(modulus) && rhs.is_reducible(modulus),
+        }
+    }
+
+    /// A lower bound on the value for any `old`, or `None` when a subtraction might go below zero.
+    /// Only constants can be subtracted, and only from something known to be at least as large.
+    fn min_value(&self) -> Option<usize> {
+        match self {
+            Expression::Old         => Some(0),
+            Expression::Number(x)   => Some(*x),
+            Expression::Binary(lhs, operator, rhs) => {
+                let (lower, rhs_lower) = (lhs.min_value()?, rhs.min_value()?);
+
+                match operator {
+                    Operator::Add       => Some(lower.saturating_add(rhs_lower)),
+                    Operator::Multiply  => Some(lower.saturating_mul(rhs_lower)),
+                    Operator::Subtract  => lower.checked_sub(rhs.constant()?),
+                    Operator::Divide | Operator::Remainder => Some(0),
+                }
+            }
+        }
+    }
+
+    /// The value of the expression if it does not depend on `old`.
+    fn constant(&self) -> Option<usize> {
+        match self {
+            Expression::Old         => None,
+            Expression::Number(x)   => Some(*x),
+            Expression::Binary(lhs, _, rhs) => {
+                lhs.constant()?;
+                rhs.constant()?;
+                self.evaluate(0)
+            }
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Old         => write!(f, "old"),
+            Expression::Number(x)   => write!(f, "{}", x),
+            Expression::Binary(lhs, operator, rhs) => {
+                let needs_parentheses = |side: &Expression, strict: bool| match side {
+                    Expression::Binary(_, inner, _) => inner.precedence() < operator.precedence()
+                        || (strict && inner.precedence() == operator.precedence()),
+                    _ => false,
+                };
+
+                let write_side = |f: &mut Formatter<'_>, side: &Expression, strict: bool| {
+                    if needs_parentheses(side, strict) { write!(f, "({})", side) } else { write!(f, "{}", side) }
+                };
+
+                write_side(f, lhs, false)?;
+                write!(f, " {} ", operator.symbol())?;
+                write_side(f, rhs, true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negative_intermediates_are_rejected() {
+        let expression = Expression::parse("(old - 10) % 3 + 10").unwrap();
+
+        assert_eq!(expression.evaluate(4), None);
+        assert_eq!(expression.evaluate(14), Some(11));
+        assert!(!expression.supports_modulo(6));
+        assert!(!Expression::parse("old * 3 - 2").unwrap().supports_modulo(6));
+    }
+
+    #[test]
+    fn reduction_matches_evaluation() {
+        let modulus = 2 * 3 * 5 * 7;
+
+        for notation in ["old * old + 3", "(old + 10) - 7", "old * 3 + 5 - 2 + old", "(old + 5) % 3 * old"] {
+            let expression = Expression::parse(notation).unwrap();
+            assert!(expression.supports_modulo(modulus), "{}", notation);
+
+            for old in 0..1000 {
+                assert_eq!(expression.evaluate_modulo(old % modulus, modulus), expression.evaluate(old).map(|x| x % modulus),
+                    "{} with old = {}", notation, old);
+            }
+        }
+    }
+}
@@ -1,183 +1,371 @@
+mod big;
+mod cycle;
+mod exact;
+mod expression;
+mod notes;
+mod rules;
+mod trace;
+
+use std::{env, fs, io};
+
+use expression::Expression;
+use rules::{Relief, Rules};
+use trace::Trace;
+
+#[derive(Clone)]
 struct MonkeyTest {
     value: usize,
     if_true: usize,
     if_false: usize
 }
 
-enum Value {
-    Previous,
-    Number(usize)
-}
-
-impl From<&str> for Value {
-    fn from(val: &str) -> Self {
-        let value = val.parse::<usize>();
-
-        match value {
-            Ok(x) => Value::Number(x),
-            Err(_) => Value::Previous,
-        }
-    }
-}
-
+#[derive(Clone)]
 struct Monkey {
     inspected: usize,
     items: Vec<usize>,
-    rhs: Value,
-    operation: fn(&Monkey, usize) -> usize,
+    operation: Expression,
     test: MonkeyTest
 }
 
+/// An item thrown by a monkey after inspecting it.
+struct Throw {
+    to: usize,
+    worry_before: usize,
+    worry_after: usize,
+}
+
 impl Monkey {
-    fn add(&self, item: usize) -> usize {
-        let rhs = match self.rhs {
-            Value::Previous => item,
-            Value::Number(x) => x,
+    /// Inspects a single item, returning the monkey it is thrown to and its new worry level, or why
+    /// the new worry level cannot be worked out.
+    fn throw(&self, worry_level: usize, relief: Relief, divisor: usize) -> Result<(usize, usize), String> {
+        let new_worry_level = match relief {
+            Relief::Divide(by)  => self.operation.evaluate(worry_level).map(|x| x / by),
+            Relief::Reduce      => self.operation.evaluate_modulo(worry_level, divisor),
         };
 
-        item + rhs
+        let worry_level = new_worry_level.ok_or_else(|| match relief {
+            Relief::Divide(_)   => format!("new = {} is out of range for old = {}", self.operation, worry_level),
+            Relief::Reduce      => format!("'{}' cannot be reduced modulo {}", self.operation, divisor),
+        })?;
+
+        if worry_level.is_multiple_of(self.test.value) {
+            Ok((self.test.if_true, worry_level))
+        } else {
+            Ok((self.test.if_false, worry_level))
+        }
     }
 
-    fn multiply(&self, item: usize) -> usize {
-        let rhs = match self.rhs {
-            Value::Previous => item,
-            Value::Number(x) => x,
-        };
+    fn inspect(&mut self, relief: Relief, divisor: usize) -> Result<Vec<Throw>, String> {
+        let mut actions = Vec::<Throw>::new();
+
+        while let Some(worry_before) = self.items.pop() {
+            let (to, worry_after) = self.throw(worry_before, relief, divisor)?;
+            actions.push(Throw { to, worry_before, worry_after });
+            self.inspected += 1;
+        }
 
-        item * rhs
+        Ok(actions)
     }
+}
+
+#[derive(Clone)]
+struct MonkeyCollective {
+    divisor: usize,
+    monkeys: Vec<Monkey>
+}
 
-    fn inspect_part1(&mut self) -> Vec<(usize, usize)> {
-        let mut actions = Vec::<(usize, usize)>::new();
+impl MonkeyCollective {
+    fn round(&mut self, relief: Relief, mut trace: Option<&mut Trace>) -> Result<(), String> {
+        if let Some(trace) = trace.as_mut() { trace.start_round(self.monkeys.len()) }
 
-        while let Some(worry_level) = self.items.pop() {
-            let worry_level = (self.operation)(&self, worry_level) / 3;
+        for index in 0..self.monkeys.len() {
+            let throws = self.monkeys[index].inspect(relief, self.divisor)
+                .map_err(|error| format!("monkey {}: {}", index, error))?;
 
-            if worry_level % self.test.value == 0 {
-                actions.push((self.test.if_true, worry_level));
-            } else {
-                actions.push((self.test.if_false, worry_level));
+            for throw in throws {
+                if let Some(trace) = trace.as_mut() { trace.record(index, &throw) }
+                self.monkeys[throw.to].items.push(throw.worry_after);
             }
-
-            self.inspected += 1;
         }
 
-        actions
+        Ok(())
     }
 
-    fn inspect_part2(&mut self, divisor: usize) -> Vec<(usize, usize)> {
-        let mut actions = Vec::<(usize, usize)>::new();
+    /// Plays all rounds of `rules`, returning the level of monkey business after them, which is `None`
+    /// if it does not fit a `usize`.
+    fn run(&mut self, rules: &Rules) -> Result<Option<usize>, String> {
+        if rules.relief == Relief::Reduce { self.check_reducible()? }
 
-        while let Some(worry_level) = self.items.pop() {
-            let worry_level = (self.operation)(&self, worry_level) % divisor;
+        for round in 1..=rules.rounds {
+            self.round(rules.relief, None).map_err(|error| format!("round {}, {}", round, error))?;
+        }
 
-            if worry_level % self.test.value == 0 {
-                actions.push((self.test.if_true, worry_level));
-            } else {
-                actions.push((self.test.if_false, worry_level));
-            }
+        Ok(self.calculate_monkey_business(rules))
+    }
 
-            self.inspected += 1;
+    fn check_reducible(&self) -> Result<(), String> {
+        match self.monkeys.iter().find(|monkey| !monkey.operation.supports_modulo(self.divisor)) {
+            Some(monkey) => Err(format!("'{}' cannot be reduced modulo {}", monkey.operation, self.divisor)),
+            None => Ok(()),
         }
+    }
+
+    fn calculate_monkey_business(&self, rules: &Rules) -> Option<usize> {
+        let mut inspected: Vec<usize> = self.monkeys.iter().map(|monkey| monkey.inspected).collect();
+
+        inspected.sort_by_key(|&x| std::cmp::Reverse(x));
+        inspected.truncate(rules.top);
 
-        actions
+        rules.combine.apply(&inspected)
     }
 }
 
-struct MonkeyCollective {
-    divisor: usize,
-    monkeys: Vec<Monkey>
+fn part1(input: &str) -> usize {
+    let mut monkey_collective = MonkeyCollective::parse(input).expect("Invalid puzzle input");
+    monkey_collective.run(&Rules::part1()).unwrap().expect("Monkey business out of range")
 }
 
-impl MonkeyCollective {
-    fn parse(s: &str) -> MonkeyCollective {
-        let monkeys = s.split_terminator("\n\n").map(|entry| {
-            let lines = entry.split_terminator('\n').collect::<Vec<&str>>();
-
-            let items = lines.get(1).unwrap()["  Starting items: ".len()..]
-                .split(", ")
-                .map(|x| x.parse::<usize>().unwrap())
-                .collect();
-
-            let test = lines.get(3).unwrap()["  Test: divisible by ".len()..].parse().unwrap();
-            let if_true = lines.get(4).unwrap()["    If true: throw to monkey ".len()..].parse().unwrap();
-            let if_false = lines.get(5).unwrap()["    If false: throw to monkey ".len()..].parse().unwrap();
-
-            let operation_line = lines.get(2).unwrap();
-            let operator_index = operation_line.chars().position(|x| x == '+' || x == '*').unwrap();
-
-            let is_operation_add = &operation_line[operator_index..operator_index+1] == "+";
-            let rhs = Value::from(&operation_line[operator_index + 2..]);
-
-            Monkey {
-                inspected: 0,
-                items,
-                rhs,
-                operation: if is_operation_add { Monkey::add } else { Monkey::multiply },
-                test: MonkeyTest { value: test, if_true, if_false }
-            }
-        }).collect();
+fn part2(input: &str) -> usize {
+    let mut monkey_collective = MonkeyCollective::parse(input).expect("Invalid puzzle input");
+    monkey_collective.run(&Rules::part2()).unwrap().expect("Monkey business out of range")
+}
 
-        let divisor = MonkeyCollective::calculate_divisor(&monkeys);
-        
-        MonkeyCollective { divisor, monkeys }
-    }
+fn read_notes(path: Option<&str>) -> io::Result<MonkeyCollective> {
+    let notes = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => String::from(include_str!("input.txt")),
+    };
 
-    fn round1(&mut self) {
-        for index in 0..self.monkeys.len() {
-            for (target, worry_level) in self.monkeys[index].inspect_part1() {
-                self.monkeys[target].items.push(worry_level);
-            }
-        }
+    Ok(MonkeyCollective::parse(&notes)?)
+}
+
+/// Prints the inspection counts after any number of rounds with the part 2 rules.
+fn fast_forward(rounds: &str, path: Option<&str>) -> io::Result<()> {
+    let rounds = rounds.parse::<u64>()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not a number of rounds", rounds)))?;
+
+    let inspected = read_notes(path)?.inspections_after(rounds).map_err(invalid_input)?;
+    println!("inspected: {:?}", inspected);
+    println!("monkey business: {}", cycle::monkey_business(&inspected));
+
+    Ok(())
+}
+
+/// Reads a file of notes and describes the monkeys in it, or what is wrong with it.
+fn check(path: &str) -> io::Result<()> {
+    let monkey_collective = read_notes(Some(path))?;
+
+    for (id, monkey) in monkey_collective.monkeys.iter().enumerate() {
+        println!("monkey {}: {} item(s), new = {}, divisible by {} ? {} : {}",
+            id, monkey.items.len(), monkey.operation, monkey.test.value, monkey.test.if_true, monkey.test.if_false);
     }
 
-    fn round2(&mut self) {
-        for index in 0..self.monkeys.len() {
-            for (target, worry_level) in self.monkeys[index].inspect_part2(self.divisor) {
-                self.monkeys[target].items.push(worry_level);
-            }
-        }
+    println!("divisor: {}", monkey_collective.divisor);
+
+    Ok(())
+}
+
+const SHOWN_OVERFLOWS: usize = 10;
+/// Beyond this, worry levels run to millions of bits and every round takes longer than the last.
+const MAX_EXACT_ROUNDS: usize = 100;
+
+/// Runs a few rounds without reducing worry levels, checks that reducing them changes nothing and shows
+/// where a simulation with plain `usize` worry levels would have gone wrong.
+fn exact(rounds: &str, path: Option<&str>) -> io::Result<()> {
+    let rounds = rounds.parse::<usize>()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not a number of rounds", rounds)))?;
+
+    if rounds > MAX_EXACT_ROUNDS {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("at most {} rounds can be run exactly", MAX_EXACT_ROUNDS)));
     }
 
-    fn calculate_divisor(monkeys: &Vec<Monkey>) -> usize {
-        monkeys.iter().fold(1, |acc, monkey| acc * monkey.test.value)
+    let monkey_collective = read_notes(path)?;
+    monkey_collective.check_reducible().map_err(invalid_input)?;
+
+    let run = monkey_collective.verify_reduction(rounds)
+        .map_err(|error| io::Error::other(format!("reducing modulo {} changes the result: {}", monkey_collective.divisor, error)))?;
+
+    println!("inspected: {:?}", run.inspected);
+    println!("reducing modulo {} gives the same result", monkey_collective.divisor);
+
+    let largest = run.items.iter().flatten().map(|x| x.bits()).max().unwrap_or(0);
+    println!("largest worry level: {} bits", largest);
+    println!("throws that overflow usize: {}", run.overflows.len());
+
+    for overflow in run.overflows.iter().take(SHOWN_OVERFLOWS) {
+        println!("  round {}: monkey {} -> monkey {}, {} bits -> {} bits",
+            overflow.round, overflow.from, overflow.to, overflow.bits_before, overflow.bits_after);
     }
 
-    fn calculate_monkey_business(&self) -> usize {
-        let mut m: Vec<&Monkey> = self.monkeys.iter().collect();
+    if run.overflows.len() > SHOWN_OVERFLOWS {
+        println!("  ...");
+    }
 
-        m.sort_by(|a, b| b.inspected.cmp(&a.inspected));
+    Ok(())
+}
 
-        let first = m[0].inspected;
-        let second = m[1].inspected;
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
 
-        first * second
+/// Reads `from=N`, `to=N`, `rounds=A-B` and `every=N` options for `trace`.
+fn parse_trace_options(options: &[&str]) -> io::Result<(trace::ThrowFilter, usize)> {
+    let mut filter = trace::ThrowFilter::default();
+    let mut sample_every = 1;
+
+    for option in options {
+        let number = |value: &str| value.parse::<usize>().map_err(|_| invalid_input(format!("'{}' is not a number", value)));
+
+        match option.split_once('=') {
+            Some(("from", value))   => filter.from = Some(number(value)?),
+            Some(("to", value))     => filter.to = Some(number(value)?),
+            Some(("every", value))  => sample_every = number(value)?,
+            Some(("rounds", value)) => filter.rounds = Some(match value.split_once('-') {
+                Some((first, last)) => number(first)?..=number(last)?,
+                None                => number(value)?..=number(value)?,
+            }),
+            _ => return Err(invalid_input(format!("unknown option '{}', expected from=, to=, rounds= or every= after any --notes <path>", option))),
+        }
     }
+
+    Ok((filter, sample_every))
 }
 
-fn part1(input: &str) -> usize {
-    let mut monkey_collective = MonkeyCollective::parse(input);
+/// Runs `rounds` rounds of either part's rules on the notes in `path`, or the puzzle input, recording them in `trace`.
+fn run_traced(part: &str, rounds: &str, path: Option<&str>, trace: &mut Trace) -> io::Result<()> {
+    let rounds = rounds.parse::<usize>().map_err(|_| invalid_input(format!("'{}' is not a number of rounds", rounds)))?;
+    let mut monkey_collective = read_notes(path)?;
 
-    for _ in 0..20 {
-        monkey_collective.round1();
+    let relief = match part {
+        "1" => Rules::part1().relief,
+        "2" => Rules::part2().relief,
+        _   => return Err(invalid_input(format!("unknown part '{}', expected 1 or 2", part))),
+    };
+
+    if relief == Relief::Reduce { monkey_collective.check_reducible().map_err(invalid_input)? }
+
+    for round in 1..=rounds {
+        monkey_collective.round(relief, Some(trace)).map_err(|error| invalid_input(format!("round {}, {}", round, error)))?;
     }
 
-    monkey_collective.calculate_monkey_business()
+    Ok(())
 }
 
-fn part2(input: &str) -> usize {
-    let mut monkey_collective = MonkeyCollective::parse(input);
+/// Prints the throws made during the first `rounds` rounds of a part that pass the filter options,
+/// playing the notes at `path` if they were given with `--notes`.
+fn print_trace(part: &str, rounds: &str, path: Option<&str>, options: &[&str]) -> io::Result<()> {
+    let (filter, sample_every) = parse_trace_options(options)?;
+    let mut trace = Trace::new(filter, sample_every);
 
-    for _ in 0..10000 {
-        monkey_collective.round2();
+    run_traced(part, rounds, path, &mut trace)?;
+    trace.throws.iter().for_each(|throw| println!("{}", throw));
+
+    Ok(())
+}
+
+/// Writes how many items every monkey inspected in each round as CSV, to `output` or standard output if it is
+/// missing or `-`.
+fn write_timeline(part: &str, rounds: &str, output: Option<&str>, path: Option<&str>) -> io::Result<()> {
+    let mut trace = Trace::timeline_only();
+    run_traced(part, rounds, path, &mut trace)?;
+
+    match output {
+        Some(output) if output != "-"   => fs::write(output, trace.timeline_csv()),
+        _                               => { print!("{}", trace.timeline_csv()); Ok(()) },
     }
+}
+
+/// Plays the puzzle with the part 1 rules changed by `options`.
+fn play(options: &[&str]) -> io::Result<()> {
+    let rules = Rules::part1().with_options(options).map_err(invalid_input)?;
+    let mut monkey_collective = read_notes(None)?;
 
-    monkey_collective.calculate_monkey_business()
+    match monkey_collective.run(&rules).map_err(invalid_input)? {
+        Some(monkey_business) => println!("monkey business: {}", monkey_business),
+        None => println!("monkey business does not fit a usize"),
+    }
+
+    Ok(())
 }
 
 fn main() {
     let input = include_str!("input.txt");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["rounds", rounds]          => Some(fast_forward(rounds, None)),
+        ["rounds", rounds, path]    => Some(fast_forward(rounds, Some(path))),
+        ["exact", rounds]           => Some(exact(rounds, None)),
+        ["exact", rounds, path]     => Some(exact(rounds, Some(path))),
+        ["trace", part, rounds, "--notes", path, options @ ..] => Some(print_trace(part, rounds, Some(path), options)),
+        ["trace", part, rounds, options @ ..]   => Some(print_trace(part, rounds, None, options)),
+        ["timeline", part, rounds]              => Some(write_timeline(part, rounds, None, None)),
+        ["timeline", part, rounds, output]      => Some(write_timeline(part, rounds, Some(output), None)),
+        ["timeline", part, rounds, output, path] => Some(write_timeline(part, rounds, Some(output), Some(path))),
+        ["play", options @ ..]                  => Some(play(options)),
+        ["check", path]             => Some(check(path)),
+        _                           => None,
+    };
+
+    if let Some(result) = command {
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
 
     println!("part1: {}", part1(input));
     println!("part2: {}", part2(input));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub const EXAMPLE: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE), 10605);
+        assert_eq!(part2(EXAMPLE), 2713310158);
+    }
+
+    #[test]
+    fn overflowing_worry_levels_are_reported() {
+        let mut monkey_collective = MonkeyCollective::parse(include_str!("input.txt")).unwrap();
+        let rules = Rules::part1().with_options(&["relief=1"]).unwrap();
+
+        let error = monkey_collective.run(&rules).unwrap_err();
+        assert!(error.contains("out of range"), "{}", error);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{io, fmt::{self, Display, Formatter}};
+
+use super::{expression::{self, Expression}, Monkey, MonkeyCollective, MonkeyTest};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    MissingHeader,
+    DuplicateMonkey,
+    /// Monkeys are numbered from zero without gaps, but this one was never declared.
+    MissingMonkey,
+    /// The id is at least the number of monkeys in the notes, so some other id must be missing.
+    IdOutOfRange(usize),
+    UnknownLine(String),
+    DuplicateField(&'static str),
+    MissingField(&'static str),
+    InvalidNumber(String),
+    InvalidOperation(expression::ParseError),
+    ZeroTest,
+    /// The product of every monkey's test value, which worry levels are reduced by, does not fit in a `usize`.
+    DivisorOverflow,
+    UnknownTarget(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The monkey's id, or its position in the notes when it has no `Monkey N:` header.
+    pub monkey: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "monkey {}: ", self.monkey)?;
+
+        match &self.kind {
+            ParseErrorKind::MissingHeader           => write!(f, "missing a 'Monkey N:' line"),
+            ParseErrorKind::DuplicateMonkey         => write!(f, "declared more than once"),
+            ParseErrorKind::MissingMonkey           => write!(f, "never declared"),
+            ParseErrorKind::IdOutOfRange(count)     => write!(f, "the notes only describe {} monkeys", count),
+            ParseErrorKind::UnknownLine(line)       => write!(f, "'{}' is not a known note", line),
+            ParseErrorKind::DuplicateField(field)   => write!(f, "'{}' is given more than once", field),
+            ParseErrorKind::MissingField(field)     => write!(f, "'{}' is missing", field),
+            ParseErrorKind::InvalidNumber(value)    => write!(f, "'{}' is not a number", value),
+            ParseErrorKind::InvalidOperation(error) => write!(f, "invalid operation: {}", error),
+            ParseErrorKind::ZeroTest                => write!(f, "cannot test for divisibility by 0"),
+            ParseErrorKind::DivisorOverflow         => write!(f, "the product of every test's divisor is too large"),
+            ParseErrorKind::UnknownTarget(target)   => write!(f, "throws to monkey {}, which does not exist", target),
+        }
+    }
+}
+
+impl From<ParseError> for io::Error {
+    fn from(error: ParseError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+    }
+}
+
+const STARTING_ITEMS: &str  = "Starting items";
+const OPERATION: &str       = "Operation";
+const TEST: &str            = "Test";
+const IF_TRUE: &str         = "If true";
+const IF_FALSE: &str        = "If false";
+
+/// Collapses runs of whitespace, so that `If   true` and `If true` are the same key.
+fn normalise(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_number(s: &str) -> Result<usize, ParseErrorKind> {
+    s.trim().parse().map_err(|_| ParseErrorKind::InvalidNumber(String::from(s.trim())))
+}
+
+/// Reads the number after `prefix`, as in `divisible by 7` or `throw to monkey 3`.
+fn parse_suffixed_number(value: &str, prefix: &str) -> Result<usize, ParseErrorKind> {
+    match normalise(value).strip_prefix(prefix) {
+        Some(number) => parse_number(number),
+        None => Err(ParseErrorKind::UnknownLine(String::from(value))),
+    }
+}
+
+fn parse_header(line: &str) -> Option<Result<usize, ParseErrorKind>> {
+    let line = normalise(line);
+    let id = line.strip_prefix("Monkey ")?.strip_suffix(':')?;
+
+    Some(parse_number(id))
+}
+
+/// The notes on one monkey, in any order and with any indentation.
+fn parse_monkey(lines: &[&str]) -> Result<Monkey, ParseErrorKind> {
+    let mut items = None;
+    let mut operation = None;
+    let mut test = None;
+    let mut if_true = None;
+    let mut if_false = None;
+
+    for line in lines {
+        let (key, value) = line.split_once(':').ok_or_else(|| ParseErrorKind::UnknownLine(String::from(*line)))?;
+
+        let (field, slot, parsed) = match normalise(key).as_str() {
+            STARTING_ITEMS => {
+                let parsed = value.split(',')
+                    .filter(|x| !x.trim().is_empty())
+                    .map(parse_number)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if items.replace(parsed).is_some() { return Err(ParseErrorKind::DuplicateField(STARTING_ITEMS)) }
+                continue;
+            },
+            OPERATION => {
+                let expression = match value.split_once('=') {
+                    Some((lhs, rhs)) if lhs.trim() == "new" => Expression::parse(rhs).map_err(ParseErrorKind::InvalidOperation)?,
+                    _ => return Err(ParseErrorKind::UnknownLine(String::from(*line))),
+                };
+
+                if operation.replace(expression).is_some() { return Err(ParseErrorKind::DuplicateField(OPERATION)) }
+                continue;
+            },
+            TEST        => (TEST, &mut test, parse_suffixed_number(value, "divisible by ")?),
+            IF_TRUE     => (IF_TRUE, &mut if_true, parse_suffixed_number(value, "throw to monkey ")?),
+            IF_FALSE    => (IF_FALSE, &mut if_false, parse_suffixed_number(value, "throw to monkey ")?),
+            _           => return Err(ParseErrorKind::UnknownLine(String::from(*line))),
+        };
+
+        if slot.replace(parsed).is_some() { return Err(ParseErrorKind::DuplicateField(field)) }
+    }
+
+    let value = test.ok_or(ParseErrorKind::MissingField(TEST))?;
+    if value == 0 { return Err(ParseErrorKind::ZeroTest) }
+
+    Ok(Monkey {
+        inspected: 0,
+        items: items.ok_or(ParseErrorKind::MissingField(STARTING_ITEMS))?,
+        operation: operation.ok_or(ParseErrorKind::MissingField(OPERATION))?,
+        test: MonkeyTest {
+            value,
+            if_true: if_true.ok_or(ParseErrorKind::MissingField(IF_TRUE))?,
+            if_false: if_false.ok_or(ParseErrorKind::MissingField(IF_FALSE))?,
+        },
+    })
+}
+
+impl MonkeyCollective {
+    /// Reads the notes on every monkey, separated by blank lines. Monkeys may be declared in any order,
+    /// but their ids must run from zero without gaps and every monkey they throw to must exist.
+    pub fn parse(s: &str) -> Result<MonkeyCollective, ParseError> {
+        let mut blocks = vec![vec![]];
+
+        for line in s.lines().map(str::trim) {
+            match (line.is_empty(), blocks.last().unwrap().is_empty()) {
+                (true, true)    => {},
+                (true, false)   => blocks.push(vec![]),
+                (false, _)      => blocks.last_mut().unwrap().push(line),
+            }
+        }
+
+        if blocks.last().unwrap().is_empty() { blocks.pop(); }
+
+        let mut declared: Vec<Option<Monkey>> = (0..blocks.len()).map(|_| None).collect();
+
+        for (position, lines) in blocks.iter().enumerate() {
+            let mut headers = lines.iter().enumerate().filter_map(|(index, line)| Some((index, parse_header(line)?)));
+
+            let (header, id) = match headers.next() {
+                Some((index, Ok(id))) => (index, id),
+                Some((_, Err(kind))) => return Err(ParseError { monkey: position, kind }),
+                None => return Err(ParseError { monkey: position, kind: ParseErrorKind::MissingHeader }),
+            };
+
+            let error = |kind| ParseError { monkey: id, kind };
+            if headers.next().is_some() { return Err(error(ParseErrorKind::DuplicateMonkey)) }
+
+            let fields = lines.iter().enumerate().filter(|&(index, _)| index != header).map(|(_, line)| *line).collect::<Vec<_>>();
+            let monkey = parse_monkey(&fields).map_err(error)?;
+
+            if id >= blocks.len() { return Err(error(ParseErrorKind::IdOutOfRange(blocks.len()))) }
+            if declared[id].replace(monkey).is_some() { return Err(error(ParseErrorKind::DuplicateMonkey)) }
+        }
+
+        let monkeys = declared.into_iter().enumerate()
+            .map(|(id, monkey)| monkey.ok_or(ParseError { monkey: id, kind: ParseErrorKind::MissingMonkey }))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (id, monkey) in monkeys.iter().enumerate() {
+            if let Some(&target) = [monkey.test.if_true, monkey.test.if_false].iter().find(|&&x| x >= monkeys.len()) {
+                return Err(ParseError { monkey: id, kind: ParseErrorKind::UnknownTarget(target) });
+            }
+        }
+
+        let divisor = monkeys.iter().enumerate().try_fold(1usize, |divisor, (id, monkey)| {
+            divisor.checked_mul(monkey.test.value).ok_or(ParseError { monkey: id, kind: ParseErrorKind::DivisorOverflow })
+        })?;
+
+        Ok(MonkeyCollective { divisor, monkeys })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTES: &str = "\
+Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 1
+    If false: throw to monkey 1
+
+Monkey 1:
+  Starting items: 54
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 0
+    If false: throw to monkey 0
+";
+
+    fn summary(monkey_collective: &MonkeyCollective) -> Vec<(Vec<usize>, String, usize, usize, usize)> {
+        monkey_collective.monkeys.iter().map(|monkey| {
+            (monkey.items.clone(), monkey.operation.to_string(), monkey.test.value, monkey.test.if_true, monkey.test.if_false)
+        }).collect()
+    }
+
+    fn error(notes: &str) -> ParseError {
+        MonkeyCollective::parse(notes).err().expect("notes should be rejected")
+    }
+
+    #[test]
+    fn order_and_whitespace_do_not_matter() {
+        let reordered = "\
+Monkey 1:
+Test:   divisible by 19
+  If false: throw to monkey 0
+      Operation: new = old + 6
+  If  true : throw to monkey 0
+  Starting items: 54
+
+
+   Monkey   0:
+  If false: throw to monkey 1
+  Starting items: 79,98
+  If true: throw to monkey 1
+Operation: new=old*19
+Test: divisible by 23";
+
+        let (expected, actual) = (MonkeyCollective::parse(NOTES).unwrap(), MonkeyCollective::parse(reordered).unwrap());
+
+        assert_eq!(summary(&actual), summary(&expected));
+        assert_eq!((actual.divisor, expected.divisor), (23 * 19, 23 * 19));
+    }
+
+    #[test]
+    fn missing_and_duplicate_monkeys() {
+        assert_eq!(error(&NOTES.replace("Monkey 1:", "Monkey 2:")), ParseError { monkey: 2, kind: ParseErrorKind::IdOutOfRange(2) });
+        assert_eq!(error(&NOTES.replace("Monkey 1:", "Monkey 0:")), ParseError { monkey: 0, kind: ParseErrorKind::DuplicateMonkey });
+        assert_eq!(error(&NOTES.replace("Monkey 1:\n", "Monkey 1:\nMonkey 1:\n")), ParseError { monkey: 1, kind: ParseErrorKind::DuplicateMonkey });
+        assert_eq!(error(&NOTES.replace("Monkey 1:\n", "")), ParseError { monkey: 1, kind: ParseErrorKind::MissingHeader });
+    }
+
+    #[test]
+    fn throws_must_go_to_a_known_monkey() {
+        let notes = NOTES.replace("If false: throw to monkey 0", "If false: throw to monkey 5");
+
+        assert_eq!(error(&notes), ParseError { monkey: 1, kind: ParseErrorKind::UnknownTarget(5) });
+    }
+
+    #[test]
+    fn monkey_ids_must_be_numbers() {
+        assert_eq!(error(&NOTES.replace("Monkey 1:", "Monkey one:")), ParseError { monkey: 1, kind: ParseErrorKind::InvalidNumber(String::from("one")) });
+        assert_eq!(error(&NOTES.replace("Monkey 0:", "Monkey -1:")), ParseError { monkey: 0, kind: ParseErrorKind::InvalidNumber(String::from("-1")) });
+    }
+
+    #[test]
+    fn errors_name_the_monkey_not_its_position() {
+        let (first, second) = NOTES.split_once("\n\n").unwrap();
+        let swapped = format!("{}\n\n{}", second.replace("divisible by 19", "divisible by 0"), first);
+
+        assert_eq!(error(&swapped), ParseError { monkey: 1, kind: ParseErrorKind::ZeroTest });
+
+        let swapped = format!("{}\n\n{}", second.replace("old + 6", "old +"), first);
+        assert_eq!(error(&swapped).monkey, 1);
+        assert!(error(&swapped).to_string().starts_with("monkey 1: invalid operation"));
+
+        let swapped = format!("{}\n\n{}", second, first.replace("Starting items: 79, 98", "Starting items: 79, x"));
+        assert_eq!(error(&swapped), ParseError { monkey: 0, kind: ParseErrorKind::InvalidNumber(String::from("x")) });
+    }
+
+    #[test]
+    fn divisor_must_fit_in_a_usize() {
+        let notes = NOTES
+            .replace("divisible by 23", &format!("divisible by {}", usize::MAX / 2))
+            .replace("divisible by 19", "divisible by 3");
+
+        assert_eq!(error(&notes), ParseError { monkey: 1, kind: ParseErrorKind::DivisorOverflow });
+    }
+}
@@ -0,0 +1,67 @@
+/// What happens to an item's worry level after a monkey has inspected it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relief {
+    /// The worry level is divided by this, rounding down, as in part 1.
+    Divide(usize),
+    /// There is no relief, but worry levels are only kept modulo the product of all tests, which
+    /// changes the outcome of none of them, as in part 2.
+    Reduce,
+}
+
+/// How the highest inspection counts are turned into the level of monkey business.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combine {
+    Product,
+    Sum,
+}
+
+impl Combine {
+    /// Combines the counts, or returns `None` when the result does not fit a `usize`.
+    pub fn apply(&self, counts: &[usize]) -> Option<usize> {
+        match self {
+            Combine::Product    => counts.iter().try_fold(1usize, |acc, &x| acc.checked_mul(x)),
+            Combine::Sum        => counts.iter().try_fold(0usize, |acc, &x| acc.checked_add(x)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    pub relief: Relief,
+    pub rounds: usize,
+    /// How many of the most active monkeys count towards the monkey business.
+    pub top: usize,
+    pub combine: Combine,
+}
+
+impl Rules {
+    pub fn part1() -> Rules {
+        Rules { relief: Relief::Divide(3), rounds: 20, top: 2, combine: Combine::Product }
+    }
+
+    pub fn part2() -> Rules {
+        Rules { relief: Relief::Reduce, rounds: 10000, top: 2, combine: Combine::Product }
+    }
+
+    /// Changes the rules with `relief=N|reduce`, `rounds=N`, `top=N` and `combine=product|sum` options.
+    pub fn with_options(mut self, options: &[&str]) -> Result<Rules, String> {
+        for option in options {
+            let number = |value: &str| value.parse::<usize>().map_err(|_| format!("'{}' is not a number", value));
+
+            match option.split_once('=') {
+                Some(("relief", "reduce"))      => self.relief = Relief::Reduce,
+                Some(("relief", value))         => self.relief = match number(value)? {
+                    0 => return Err(String::from("cannot divide worry levels by 0")),
+                    by => Relief::Divide(by),
+                },
+                Some(("rounds", value))         => self.rounds = number(value)?,
+                Some(("top", value))            => self.top = number(value)?,
+                Some(("combine", "product"))    => self.combine = Combine::Product,
+                Some(("combine", "sum"))        => self.combine = Combine::Sum,
+                _ => return Err(format!("unknown option '{}', expected relief=, rounds=, top= or combine=", option)),
+            }
+        }
+
+        Ok(self)
+    }
+}
@@ -0,0 +1,107 @@
+use std::{fmt::{self, Display, Formatter}, ops::RangeInclusive};
+
+use super::Throw;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrowRecord {
+    pub round: usize,
+    pub from: usize,
+    pub to: usize,
+    pub worry_before: usize,
+    pub worry_after: usize,
+}
+
+impl Display for ThrowRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "round {}: monkey {} -> monkey {}, worry {} -> {}",
+            self.round, self.from, self.to, self.worry_before, self.worry_after)
+    }
+}
+
+/// Which throws to keep; `None` matches anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThrowFilter {
+    pub from: Option<usize>,
+    pub to: Option<usize>,
+    pub rounds: Option<RangeInclusive<usize>>,
+}
+
+impl ThrowFilter {
+    fn matches(&self, record: &ThrowRecord) -> bool {
+        self.from.is_none_or(|from| record.from == from)
+            && self.to.is_none_or(|to| record.to == to)
+            && self.rounds.as_ref().is_none_or(|rounds| rounds.contains(&record.round))
+    }
+}
+
+/// Records the throws made during a number of rounds, and how many items every monkey inspected in each.
+pub struct Trace {
+    /// Which throws to keep, or `None` to keep none and only count inspections.
+    filter: Option<ThrowFilter>,
+    /// Only every `sample_every`th throw matching the filter is kept, starting with the first.
+    sample_every: usize,
+    matched: usize,
+    pub throws: Vec<ThrowRecord>,
+    /// For every round, how many items each monkey inspected during it, whatever the filter.
+    pub timeline: Vec<Vec<usize>>,
+}
+
+impl Trace {
+    pub fn new(filter: ThrowFilter, sample_every: usize) -> Trace {
+        Trace { filter: Some(filter), sample_every: sample_every.max(1), matched: 0, throws: vec![], timeline: vec![] }
+    }
+
+    /// A trace that records no throws, only the timeline.
+    pub fn timeline_only() -> Trace {
+        Trace { filter: None, sample_every: 1, matched: 0, throws: vec![], timeline: vec![] }
+    }
+
+    pub fn start_round(&mut self, monkeys: usize) {
+        self.timeline.push(vec![0; monkeys]);
+    }
+
+    pub fn record(&mut self, from: usize, throw: &Throw) {
+        let round = self.timeline.len();
+        self.timeline[round - 1][from] += 1;
+
+        let Some(filter) = &self.filter else { return };
+
+        let record = ThrowRecord { round, from, to: throw.to, worry_before: throw.worry_before, worry_after: throw.worry_after };
+        if !filter.matches(&record) { return }
+
+        if self.matched.is_multiple_of(self.sample_every) {
+            self.throws.push(record);
+        }
+
+        self.matched += 1;
+    }
+
+    /// The inspections per monkey per round, one round per row.
+    pub fn timeline_csv(&self) -> String {
+        let monkeys = self.timeline.first().map_or(0, Vec::len);
+        let mut csv = (0..monkeys).fold(String::from("round"), |line, monkey| line + &format!(",monkey {}", monkey));
+
+        for (index, counts) in self.timeline.iter().enumerate() {
+            csv.push_str(&format!("\n{}", index + 1));
+            counts.iter().for_each(|count| csv.push_str(&format!(",{}", count)));
+        }
+
+        csv.push('\n');
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeline_only_keeps_no_throws() {
+        let mut trace = Trace::timeline_only();
+        trace.start_round(2);
+        trace.record(1, &Throw { to: 0, worry_before: 3, worry_after: 1 });
+
+        assert!(trace.throws.is_empty());
+        assert_eq!(trace.timeline, vec![vec![0, 1]]);
+    }
+}
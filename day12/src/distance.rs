@@ -0,0 +1,104 @@
+use std::collections::VecDeque;
+
+use super::{search::{climb_at_most_one, Position}, Grid};
+
+/// The fewest steps from every square to `E`, or `None` for squares that cannot reach it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceField {
+    pub width: usize,
+    pub height: usize,
+    pub distances: Vec<Option<usize>>,
+}
+
+impl Grid {
+    /// Finds the distance from every square to `E` at once, by searching backwards from `E`.
+    ///
+    /// Walking a route backwards turns every climb into a descent, so the search may go from a square
+    /// to a neighbour exactly when that neighbour could climb up to the square. The distance at which the
+    /// search reaches a square is then the length of the shortest route from it up to `E`.
+    pub fn distances_to_end(&self) -> DistanceField {
+        let end = self.index(self.end);
+        let mut distances = vec![None; self.values.len()];
+        let mut queue = VecDeque::from([end]);
+
+        distances[end] = Some(0);
+
+        while let Some(current) = queue.pop_front() {
+            let distance = distances[current].unwrap();
+
+            for next in self.neighbours(current) {
+                if distances[next].is_some() || !climb_at_most_one(self.values[next], self.values[current]) { continue }
+
+                distances[next] = Some(distance + 1);
+                queue.push_back(next);
+            }
+        }
+
+        DistanceField { width: self.width, height: self.height, distances }
+    }
+}
+
+impl DistanceField {
+    fn position(&self, index: usize) -> Position {
+        (index % self.width, index / self.width)
+    }
+
+    pub fn get(&self, (x, y): Position) -> Option<usize> {
+        if x >= self.width || y >= self.height { return None }
+        self.distances[x + y * self.width]
+    }
+
+    /// The square of the given elevation closest to `E`, and its distance.
+    pub fn best_start(&self, grid: &Grid, elevation: u8) -> Option<(Position, usize)> {
+        self.distances.iter().enumerate()
+            .filter(|&(index, _)| grid.values[index] == elevation)
+            .filter_map(|(index, distance)| Some((self.position(index), (*distance)?)))
+            .min_by_key(|&(_, distance)| distance)
+    }
+
+    pub fn unreachable(&self) -> Vec<Position> {
+        (0..self.distances.len()).filter(|&index| self.distances[index].is_none()).map(|index| self.position(index)).collect()
+    }
+
+    /// The squares at most `steps` steps away from `E`.
+    pub fn within(&self, steps: usize) -> Vec<Position> {
+        (0..self.distances.len()).filter(|&index| self.distances[index].is_some_and(|x| x <= steps)).map(|index| self.position(index)).collect()
+    }
+
+    /// One row of distances per line, leaving squares that cannot reach `E` empty.
+    pub fn to_csv(&self) -> String {
+        self.distances.chunks(self.width)
+            .map(|row| row.iter().map(|distance| distance.map_or(String::new(), |x| x.to_string())).collect::<Vec<_>>().join(","))
+            .map(|line| line + "\n")
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{part1, part2, Grid};
+
+    const EXAMPLE: &str = "Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi\n";
+
+    #[test]
+    fn distances_agree_with_both_parts() {
+        for grid in [Grid::parse(EXAMPLE), Grid::parse(include_str!("input.txt"))] {
+            let field = grid.distances_to_end();
+
+            assert_eq!(field.get(grid.start), Some(part1(&grid)));
+            assert_eq!(field.best_start(&grid, 0).map(|(_, distance)| distance), Some(part2(&grid)));
+        }
+    }
+
+    #[test]
+    fn example_distances() {
+        let grid = Grid::parse(EXAMPLE);
+        let field = grid.distances_to_end();
+
+        assert_eq!(field.get(grid.start), Some(31));
+        assert_eq!(field.get(grid.end), Some(0));
+        assert_eq!(field.best_start(&grid, 0), Some(((0, 4), 29)));
+        assert_eq!(field.within(1), vec![(4, 2), (5, 2)]);
+        assert!(field.unreachable().is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use super::{
+    generate::{generate, Rng, Terrain},
+    search::{climb_at_most_one, unit_cost, Path, Position},
+    hiking_trail, uphill_cost, Grid,
+};
+
+/// The cheapest cost from `from` to every square, found the slow and obvious way: repeatedly settle
+/// the unsettled square with the lowest cost. Only meant for checking the real searches on small maps.
+fn reference_costs(grid: &Grid, from: Position, can_step: impl Fn(u8, u8) -> bool, cost: impl Fn(u8, u8) -> usize) -> Vec<Option<usize>> {
+    let mut costs = vec![None; grid.values.len()];
+    let mut settled = vec![false; grid.values.len()];
+
+    costs[grid.index(from)] = Some(0);
+
+    while let Some(current) = (0..costs.len()).filter(|&x| !settled[x] && costs[x].is_some()).min_by_key(|&x| costs[x]) {
+        settled[current] = true;
+
+        for next in grid.neighbours(current) {
+            if !can_step(grid.values[current], grid.values[next]) { continue }
+
+            let next_cost = costs[current].unwrap() + cost(grid.values[current], grid.values[next]);
+            if costs[next].is_none_or(|x| next_cost < x) { costs[next] = Some(next_cost) }
+        }
+    }
+
+    costs
+}
+
+/// Checks that `path` only takes allowed steps between neighbours and really costs what it says.
+fn check_path(grid: &Grid, path: &Path, can_step: impl Fn(u8, u8) -> bool, cost: impl Fn(u8, u8) -> usize) -> Result<(), String> {
+    let mut total = 0;
+
+    for step in path.positions.windows(2) {
+        let (from, to) = (step[0], step[1]);
+        let (a, b) = (grid.values[grid.index(from)], grid.values[grid.index(to)]);
+
+        if from.0.abs_diff(to.0) + from.1.abs_diff(to.1) != 1 { return Err(format!("{:?} -> {:?} is not a single step", from, to)) }
+        if !can_step(a, b) { return Err(format!("{:?} -> {:?} climbs from {} to {}", from, to, a, b)) }
+
+        total += cost(a, b);
+    }
+
+    if total != path.cost { return Err(format!("the path costs {} but claims {}", total, path.cost)) }
+
+    Ok(())
+}
+
+fn agree(what: &str, values: &[Option<usize>]) -> Result<Option<usize>, String> {
+    if values.windows(2).any(|pair| pair[0] != pair[1]) { return Err(format!("{} disagree: {:?}", what, values)) }
+    Ok(values[0])
+}
+
+/// Runs every search on one map and checks that they agree with each other and the reference.
+fn check_map(map: &str) -> Result<(), String> {
+    let grid = Grid::parse(map);
+    let rows = map.lines().collect::<Vec<_>>();
+
+    if (grid.width, grid.height) != (rows[0].len(), rows.len()) { return Err(String::from("the map was parsed with the wrong size")) }
+    if rows[grid.start.1].as_bytes()[grid.start.0] != b'S' { return Err(String::from("S was parsed in the wrong place")) }
+    if rows[grid.end.1].as_bytes()[grid.end.0] != b'E' { return Err(String::from("E was parsed in the wrong place")) }
+
+    let is_end = |position: Position| position == grid.end;
+    let reference = reference_costs(&grid, grid.start, climb_at_most_one, unit_cost);
+
+    let bfs = grid.bfs(grid.start, is_end, climb_at_most_one);
+    let dijkstra = grid.dijkstra(grid.start, is_end, climb_at_most_one, unit_cost);
+    let a_star = grid.a_star(grid.start, grid.end, climb_at_most_one, unit_cost);
+
+    for path in [&bfs, &dijkstra, &a_star].into_iter().flatten() {
+        check_path(&grid, path, climb_at_most_one, unit_cost)?;
+    }
+
+    let steps = agree("the fewest steps from S to E", &[
+        reference[grid.index(grid.end)],
+        bfs.map(|path| path.steps()),
+        dijkstra.map(|path| path.cost),
+        a_star.map(|path| path.cost),
+        grid.distances_to_end().get(grid.start),
+    ])?;
+
+    if steps.is_none() { return Err(String::from("E cannot be reached from S")) }
+
+    let reference = reference_costs(&grid, grid.start, climb_at_most_one, uphill_cost);
+    let dijkstra = grid.dijkstra(grid.start, is_end, climb_at_most_one, uphill_cost);
+    let a_star = grid.a_star(grid.start, grid.end, climb_at_most_one, uphill_cost);
+
+    for path in [&dijkstra, &a_star].into_iter().flatten() {
+        check_path(&grid, path, climb_at_most_one, uphill_cost)?;
+    }
+
+    agree("the cheapest uphill cost from S to E", &[
+        reference[grid.index(grid.end)],
+        dijkstra.map(|path| path.cost),
+        a_star.map(|path| path.cost),
+    ])?;
+
+    // The climb rule reversed, as `Grid::distances_to_end` explains.
+    let descend = |from: u8, to: u8| climb_at_most_one(to, from);
+    let reference = reference_costs(&grid, grid.end, descend, unit_cost);
+    let field = grid.distances_to_end();
+
+    if field.distances != reference { return Err(String::from("the distance field disagrees with the reference")) }
+
+    let lowest = (0..reference.len()).filter(|&index| grid.values[index] == 0).filter_map(|index| reference[index]).min();
+
+    agree("the fewest steps from any a to E", &[
+        lowest,
+        hiking_trail(&grid).map(|path| path.steps()),
+        field.best_start(&grid, 0).map(|(_, distance)| distance),
+    ])?;
+
+    Ok(())
+}
+
+pub const MAX_SIZE: usize = 40;
+
+/// Generates `count` maps of random sizes and shapes and checks every search on them, stopping at the
+/// first map they get wrong and returning it with what went wrong.
+pub fn fuzz(count: usize, seed: u64) -> Result<usize, (String, String)> {
+    let mut rng = Rng::new(seed);
+    let mut checked = 0;
+
+    while checked < count {
+        let terrain = Terrain {
+            width: 1 + rng.below(MAX_SIZE),
+            height: 1 + rng.below(MAX_SIZE),
+            ridges: rng.below(5),
+            valleys: rng.below(5),
+        };
+
+        let Some(map) = generate(&terrain, rng.next()) else { continue };
+        check_map(&map).map_err(|error| (map, error))?;
+
+        checked += 1;
+    }
+
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn searches_agree_on_the_example() {
+        check_map("Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi\n").unwrap();
+    }
+
+    #[test]
+    fn searches_agree_on_generated_maps() {
+        for seed in [crate::DEFAULT_SEED, 1, 0xdead_beef] {
+            if let Err((map, error)) = fuzz(40, seed) {
+                panic!("seed {}: {}\n{}", seed, error, map);
+            }
+        }
+    }
+}
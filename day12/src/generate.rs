@@ -0,0 +1,157 @@
+use super::search::Position;
+
+const MAX_ELEVATION: u8 = 25;
+
+/// A xorshift generator, good enough for making up heightmaps.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed.max(1) }
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The shape of a generated heightmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Terrain {
+    pub width: usize,
+    pub height: usize,
+    /// Straight lines of high ground across the map.
+    pub ridges: usize,
+    /// Straight lines of low ground across the map.
+    pub valleys: usize,
+}
+
+/// A straight line through the map, with the height it adds or removes falling off away from it.
+struct Feature {
+    origin: (f64, f64),
+    direction: (f64, f64),
+    width: f64,
+    amplitude: f64,
+}
+
+impl Feature {
+    fn random(rng: &mut Rng, terrain: &Terrain, amplitude: f64) -> Feature {
+        let angle = rng.unit() * std::f64::consts::PI;
+
+        Feature {
+            origin: (rng.unit() * terrain.width as f64, rng.unit() * terrain.height as f64),
+            direction: (angle.cos(), angle.sin()),
+            width: 1.0 + rng.unit() * (terrain.width.min(terrain.height) as f64 / 4.0),
+            amplitude,
+        }
+    }
+
+    fn height_at(&self, x: f64, y: f64) -> f64 {
+        let (dx, dy) = (x - self.origin.0, y - self.origin.1);
+        let distance = (dx * self.direction.1 - dy * self.direction.0).abs();
+
+        self.amplitude * (1.0 - distance / self.width).max(0.0)
+    }
+}
+
+/// A random walk through the map that never visits a square twice, found with a randomised depth first
+/// search from `start`: the route to whichever square the search reached last.
+fn random_route(rng: &mut Rng, terrain: &Terrain, start: Position) -> Vec<Position> {
+    let (width, height) = (terrain.width, terrain.height);
+    let mut visited = vec![false; width * height];
+    let mut stack = vec![start];
+    let mut longest = vec![start];
+
+    visited[start.0 + start.1 * width] = true;
+
+    while let Some(&(x, y)) = stack.last() {
+        let mut neighbours = [(0, -1), (0, 1), (-1, 0), (1, 0)].into_iter()
+            .filter_map(|(dx, dy)| Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?)))
+            .filter(|&(x, y)| x < width && y < height && !visited[x + y * width])
+            .collect::<Vec<_>>();
+
+        if neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = neighbours.swap_remove(rng.below(neighbours.len()));
+        visited[next.0 + next.1 * width] = true;
+        stack.push(next);
+
+        if stack.len() > longest.len() { longest = stack.clone() }
+    }
+
+    longest
+}
+
+/// Visits every square, row by row, going back and forth.
+fn serpentine_route(terrain: &Terrain) -> Vec<Position> {
+    (0..terrain.height).flat_map(|y| {
+        let row = (0..terrain.width).map(move |x| (x, y));
+        let row: Box<dyn Iterator<Item = Position>> = if y % 2 == 0 { Box::new(row) } else { Box::new(row.rev()) };
+        row
+    }).collect()
+}
+
+/// Generates a heightmap in the puzzle's format on which `E` can always be reached from `S`.
+///
+/// The terrain is made of random ridges and valleys. Then a route long enough to climb from `a` to `z`
+/// is picked, preferably a random one, and its squares are levelled into a ramp that never climbs more
+/// than one at a time. Returns `None` when the map has too few squares for such a route.
+pub fn generate(terrain: &Terrain, seed: u64) -> Option<String> {
+    let (width, height) = (terrain.width, terrain.height);
+    if width * height <= MAX_ELEVATION as usize { return None }
+
+    let mut rng = Rng::new(seed);
+    let mut features = (0..terrain.ridges).map(|_| Feature::random(&mut rng, terrain, MAX_ELEVATION as f64 * 0.6)).collect::<Vec<_>>();
+    features.extend((0..terrain.valleys).map(|_| Feature::random(&mut rng, terrain, -(MAX_ELEVATION as f64) * 0.6)));
+
+    let mut elevations = (0..width * height).map(|index| {
+        let (x, y) = ((index % width) as f64, (index / width) as f64);
+        let noise = rng.unit() * 4.0 - 2.0;
+        let elevation = MAX_ELEVATION as f64 / 2.0 + noise + features.iter().map(|feature| feature.height_at(x, y)).sum::<f64>();
+
+        elevation.round().clamp(0.0, MAX_ELEVATION as f64) as u8
+    }).collect::<Vec<_>>();
+
+    let start = (rng.below(width), rng.below(height));
+    let mut route = random_route(&mut rng, terrain, start);
+    if route.len() <= MAX_ELEVATION as usize { route = serpentine_route(terrain) }
+
+    let steps = route.len() - 1;
+    for (step, &(x, y)) in route.iter().enumerate() {
+        elevations[x + y * width] = (step * MAX_ELEVATION as usize / steps) as u8;
+    }
+
+    let (start, end) = (route[0], route[steps]);
+
+    let mut map = String::with_capacity((width + 1) * height);
+    for y in 0..height {
+        for x in 0..width {
+            map.push(match (x, y) {
+                position if position == start   => 'S',
+                position if position == end     => 'E',
+                _ => (b'a' + elevations[x + y * width]) as char,
+            });
+        }
+
+        map.push('\n');
+    }
+
+    Some(map)
+}
@@ -1,4 +1,12 @@
-use std::collections::{HashSet, VecDeque};
+mod distance;
+mod fuzz;
+mod generate;
+mod render;
+mod search;
+
+use std::{env, fs};
+
+use search::{climb_at_most_one, unit_cost, Path, Position};
 
 #[derive(Debug)]
 struct Grid {
@@ -9,14 +17,6 @@ struct Grid {
     values: Vec<u8>
 }
 
-#[derive(Debug)]
-struct Node {
-    step: usize,
-    x: usize,
-    y: usize,
-    value: u8,
-}
-
 impl Grid {
     fn parse(s: &str) -> Grid {
         let lines = s.split_terminator('\n').collect::<Vec<_>>();
@@ -50,132 +50,180 @@ impl Grid {
             values,
         }
     }
+}
 
-    fn get(&self, x: isize, y: isize) -> Option<Node> {
-        if x < 0 || y < 0 { return None }
-        let (x, y) = (x as usize, y as usize);
-        if x >= self.width as usize || y >= self.height { return None }
-
-        let index = x + y * self.width;
-        Some(Node { step: 0, x, y, value: self.values[index] })
-    }
-
-    fn start_node(&self) -> Option<Node> {
-        let (x, y) = self.start;
+/// The path with the fewest steps from `S` to `E`.
+fn hill_climb(grid: &Grid) -> Option<Path> {
+    grid.bfs(grid.start, |position| position == grid.end, climb_at_most_one)
+}
 
-        self.get(x as isize, y as isize)
-    }
+/// The path with the fewest steps from `E` down to any square of elevation `a`, following the climb rule
+/// backwards as `Grid::distances_to_end` explains.
+fn hiking_trail(grid: &Grid) -> Option<Path> {
+    grid.bfs(grid.end, |(x, y)| grid.values[x + y * grid.width] == 0, |from, to| climb_at_most_one(to, from))
+}
 
-    fn end_node(&self) -> Option<Node> {
-        let (x, y) = self.end;
+fn part1(grid: &Grid) -> usize {
+    hill_climb(grid).expect("No path from S to E").steps()
+}
 
-        self.get(x as isize, y as isize)
-    }
+fn part2(grid: &Grid) -> usize {
+    hiking_trail(grid).expect("No path from any a to E").steps()
+}
 
-    fn get_adjacent_nodes(&self, node: &Node) -> Vec<Node> {
-        let mut nodes = Vec::new();
+/// The shortest route from `S` to `E`.
+fn route1(grid: &Grid) -> Option<Vec<(usize, usize)>> {
+    hill_climb(grid).map(|path| path.positions)
+}
 
-        let (x, y) = (node.x as isize, node.y as isize);
+/// The shortest route from any square of elevation `a` to `E`.
+fn route2(grid: &Grid) -> Option<Vec<(usize, usize)>> {
+    // The search went down from `E`, so its route has to be walked the other way around.
+    hiking_trail(grid).map(|path| path.positions.into_iter().rev().collect())
+}
 
-        if let Some(up)     = self.get(x, y - 1) { nodes.push(up) }
-        if let Some(down)   = self.get(x, y + 1) { nodes.push(down) }
-        if let Some(left)   = self.get(x - 1, y) { nodes.push(left) }
-        if let Some(right)  = self.get(x + 1, y) { nodes.push(right) }
+/// Climbing costs this much per step, walking on the level or descending only 1.
+const UPHILL_COST: usize = 3;
 
-        nodes
-    }
+fn uphill_cost(from: u8, to: u8) -> usize {
+    if to > from { UPHILL_COST } else { 1 }
 }
 
-#[derive(Debug)]
-struct BFS {
-    queue: VecDeque<Node>,
-    visited: HashSet<(usize, usize)>
-}
+/// Finds a path from `S` to `E` with one of the search algorithms and cost models, and prints it.
+fn search(grid: &Grid, algorithm: &str, cost_model: &str) -> Result<(), String> {
+    let cost: fn(u8, u8) -> usize = match cost_model {
+        "unit"      => unit_cost,
+        "uphill"    => uphill_cost,
+        _           => return Err(format!("unknown cost model '{}', expected unit or uphill", cost_model)),
+    };
 
-impl BFS {
-    fn new() -> BFS {
-        BFS {
-            queue: VecDeque::new(),
-            visited: HashSet::new(),
-        }
-    }
+    let is_end = |position: Position| position == grid.end;
 
-    fn find_end(&mut self, grid: &Grid) -> Option<Node> {
-        let root = grid.start_node().unwrap();
-        self.queue.push_back(root);
+    let path = match algorithm {
+        "bfs"       if cost_model == "unit" => grid.bfs(grid.start, is_end, climb_at_most_one),
+        "bfs"       => return Err(String::from("bfs only finds the path with the fewest steps, use the unit cost model")),
+        "dijkstra"  => grid.dijkstra(grid.start, is_end, climb_at_most_one, cost),
+        "astar"     => grid.a_star(grid.start, grid.end, climb_at_most_one, cost),
+        _           => return Err(format!("unknown algorithm '{}', expected bfs, dijkstra or astar", algorithm)),
+    };
 
-        while let Some(v) = self.queue.pop_front() {
-            self.visited.insert((v.x, v.y));
+    let path = path.ok_or("there is no path from S to E")?;
 
-            if (v.x, v.y) == grid.end { return Some(v); }
+    println!("cost: {}", path.cost);
+    println!("steps: {}", path.steps());
+    println!("path: {}", format_positions(&path.positions));
 
-            let adjacent_nodes: Vec<Node> = grid.get_adjacent_nodes(&v).into_iter().filter(|x| {
-                let current = &v;
-                let neighbor = x;
+    Ok(())
+}
 
-                current.value + 1 >= neighbor.value
-            }).collect();
+const IMAGE_SCALE: usize = 6;
 
-            for mut edge in adjacent_nodes {
-                if self.visited.contains(&(edge.x, edge.y)) { continue }
-                self.visited.insert((edge.x, edge.y));
+/// Writes the heightmap with the route of either part drawn over it as a PPM image.
+fn write_image(grid: &Grid, path: &str, part: &str) -> Result<(), String> {
+    let route = route(grid, part)?;
+    fs::write(path, render::render_ppm(grid, &route, IMAGE_SCALE)).map_err(|error| error.to_string())
+}
 
-                edge.step = v.step + 1;
+fn route(grid: &Grid, part: &str) -> Result<Vec<(usize, usize)>, String> {
+    let route = match part {
+        "1" => route1(grid).ok_or("there is no path from S to E")?,
+        "2" => route2(grid).ok_or("there is no path from any a to E")?,
+        _   => return Err(format!("unknown part '{}', expected 1 or 2", part)),
+    };
 
-                self.queue.push_back(edge);
-            }
-        }
+    Ok(route)
+}
 
-        None
+fn parse_elevation(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        [letter @ b'a'..=b'z'] => Ok(letter - b'a'),
+        _ => Err(format!("'{}' is not an elevation from a to z", s)),
     }
+}
 
-    fn find_hiking_trail(&mut self, grid: &Grid) -> Option<Node> {
-        let root = grid.end_node().unwrap();
-        self.queue.push_back(root);
-
-        while let Some(v) = self.queue.pop_front() {
-            self.visited.insert((v.x, v.y));
-
-            if v.value == 0 { return Some(v); }
+fn format_positions(positions: &[(usize, usize)]) -> String {
+    positions.iter().map(|(x, y)| format!("({}, {})", x, y)).collect::<Vec<_>>().join(" ")
+}
 
-            let adjacent_nodes: Vec<Node> = grid.get_adjacent_nodes(&v).into_iter().filter(|x| {
-                let current = &v;
-                let neighbor = x;
+/// Answers a question about the distance from every square to `E`.
+fn distances(grid: &Grid, query: &[&str]) -> Result<(), String> {
+    let field = grid.distances_to_end();
+
+    match query {
+        [] => print!("{}", field.to_csv()),
+        ["export", path] => fs::write(path, field.to_csv()).map_err(|error| error.to_string())?,
+        ["best-start", elevation] => match field.best_start(grid, parse_elevation(elevation)?) {
+            Some(((x, y), distance)) => println!("({}, {}): {} steps", x, y, distance),
+            None => println!("no square of elevation {} can reach E", elevation),
+        },
+        ["unreachable"] => println!("{}", format_positions(&field.unreachable())),
+        ["within", steps] => {
+            let steps = steps.parse().map_err(|_| format!("'{}' is not a number of steps", steps))?;
+            println!("{}", format_positions(&field.within(steps)));
+        },
+        _ => return Err(String::from("expected export <path>, best-start <elevation>, unreachable or within <steps>")),
+    }
 
-                current.value <= neighbor.value + 1
-            }).collect();
+    Ok(())
+}
 
-            for mut edge in adjacent_nodes {
-                if self.visited.contains(&(edge.x, edge.y)) { continue }
-                self.visited.insert((edge.x, edge.y));
+const DEFAULT_SEED: u64 = 0x2022_1212;
 
-                edge.step = v.step + 1;
+/// Prints a random heightmap on which `E` can be reached from `S`, optionally followed by a seed and
+/// the number of ridges and valleys.
+fn generate_map(width: &str, height: &str, options: &[&str]) -> Result<(), String> {
+    let number = |s: &str| s.parse::<usize>().map_err(|_| format!("'{}' is not a number", s));
+    let option = |index: usize, default: usize| options.get(index).map_or(Ok(default), |x| number(x));
 
-                self.queue.push_back(edge);
-            }
-        }
+    let terrain = generate::Terrain { width: number(width)?, height: number(height)?, ridges: option(1, 2)?, valleys: option(2, 2)? };
+    let seed = option(0, DEFAULT_SEED as usize)? as u64;
 
-        None
-    }
+    let map = generate::generate(&terrain, seed).ok_or("the map must have more than 25 squares to climb from a to z")?;
+    print!("{}", map);
 
+    Ok(())
 }
 
-fn part1(grid: &Grid) -> usize {
-    let mut bfs = BFS::new();
-    let end_node = bfs.find_end(grid).unwrap();
-    end_node.step
-}
+/// Checks that all searches agree on `count` generated maps.
+fn run_fuzz(count: &str, seed: Option<&str>) -> Result<(), String> {
+    let count = count.parse().map_err(|_| format!("'{}' is not a number of maps", count))?;
+    let seed = seed.map_or(Ok(DEFAULT_SEED), |x| x.parse().map_err(|_| format!("'{}' is not a seed", x)))?;
 
-fn part2(grid: &Grid) -> usize {
-    let mut bfs = BFS::new();
-    let end_node = bfs.find_hiking_trail(grid).unwrap();
-    end_node.step
+    match fuzz::fuzz(count, seed) {
+        Ok(checked) => { println!("all searches agree on {} maps", checked); Ok(()) },
+        Err((map, error)) => Err(format!("{}\n{}", error, map)),
+    }
 }
 
 fn main() {
     let input = include_str!("input.txt");
     let grid = Grid::parse(input);
 
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["search", algorithm]               => Some(search(&grid, algorithm, "unit")),
+        ["search", algorithm, cost_model]   => Some(search(&grid, algorithm, cost_model)),
+        ["route"]                           => Some(route(&grid, "1").map(|route| println!("{}", render::render_route(&grid, &route)))),
+        ["route", part]                     => Some(route(&grid, part).map(|route| println!("{}", render::render_route(&grid, &route)))),
+        ["image", path]                     => Some(write_image(&grid, path, "1")),
+        ["image", path, part]               => Some(write_image(&grid, path, part)),
+        ["distances", query @ ..]           => Some(distances(&grid, query)),
+        ["generate", width, height, options @ ..]   => Some(generate_map(width, height, options)),
+        ["fuzz", count]                     => Some(run_fuzz(count, None)),
+        ["fuzz", count, seed]               => Some(run_fuzz(count, Some(seed))),
+        _                                   => None,
+    };
+
+    if let Some(result) = command {
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
     println!("part1: {}", part1(&grid));
     println!("part2: {}", part2(&grid));
 }
@@ -0,0 +1,133 @@
+use super::Grid;
+
+type Rgb = (u8, u8, u8);
+
+const ROUTE: Rgb = (230, 30, 30);
+
+/// Draws the route the way the puzzle does: every square along it shows which way to go next, the
+/// end is marked `E` and everything else is `.`.
+pub fn render_route(grid: &Grid, route: &[(usize, usize)]) -> String {
+    let mut cells = vec![b'.'; grid.width * grid.height];
+
+    for step in route.windows(2) {
+        let ((x, y), (next_x, next_y)) = (step[0], step[1]);
+
+        cells[x + y * grid.width] = match (next_x.cmp(&x), next_y.cmp(&y)) {
+            (_, std::cmp::Ordering::Less)       => b'^',
+            (_, std::cmp::Ordering::Greater)    => b'v',
+            (std::cmp::Ordering::Less, _)       => b'<',
+            _                                   => b'>',
+        };
+    }
+
+    if let Some(&(x, y)) = route.last() { cells[x + y * grid.width] = b'E' }
+
+    cells.chunks(grid.width)
+        .map(|row| String::from_utf8_lossy(row).into_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn lerp(from: Rgb, to: Rgb, t: f64) -> Rgb {
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t.clamp(0.0, 1.0)).round() as u8;
+    (channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+/// From green valleys through brown slopes to snowy peaks.
+fn elevation_colour(elevation: u8) -> Rgb {
+    let t = elevation as f64 / 25.0;
+
+    if t < 0.5 { lerp((30, 90, 40), (150, 110, 60), t * 2.0) } else { lerp((150, 110, 60), (245, 245, 250), t * 2.0 - 1.0) }
+}
+
+/// Renders the heightmap coloured by elevation as a binary PPM image with the route drawn over it,
+/// with every square drawn as a `scale` by `scale` block.
+pub fn render_ppm(grid: &Grid, route: &[(usize, usize)], scale: usize) -> Vec<u8> {
+    let mut colours = grid.values.iter().map(|&elevation| elevation_colour(elevation)).collect::<Vec<_>>();
+    route.iter().for_each(|&(x, y)| colours[x + y * grid.width] = ROUTE);
+
+    let (width, height) = (grid.width * scale, grid.height * scale);
+    let mut output = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = colours[x / scale + y / scale * grid.width];
+            output.extend([r, g, b]);
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{route1, route2};
+
+    const EXAMPLE: &str = "Sabqponm\nabcryxxl\naccszExk\nacctuvwj\nabdefghi\n";
+
+    #[test]
+    fn route_is_drawn_like_the_puzzle() {
+        const DRAWN: &str = "v..v<<<<\n>v.vv<<^\n.>vv>E^^\n..v>>>^^\n..>>>>>^";
+
+        let grid = Grid::parse(EXAMPLE);
+        let cells = DRAWN.lines().map(str::as_bytes).collect::<Vec<_>>();
+
+        // Follows the arrows of the drawing in the puzzle statement from `S` to `E`.
+        let mut route = vec![grid.start];
+        while let Some(&(x, y)) = route.last().filter(|&&position| position != grid.end) {
+            route.push(match cells[y][x] {
+                b'^' => (x, y - 1),
+                b'v' => (x, y + 1),
+                b'<' => (x - 1, y),
+                _    => (x + 1, y),
+            });
+        }
+
+        assert_eq!(route.len(), route1(&grid).unwrap().len());
+        assert_eq!(render_route(&grid, &route), DRAWN);
+    }
+
+    #[test]
+    fn every_square_on_the_route_is_marked() {
+        let grid = Grid::parse(EXAMPLE);
+
+        for route in [route1(&grid).unwrap(), route2(&grid).unwrap()] {
+            let rendered = render_route(&grid, &route).replace('\n', "");
+            let marked = rendered.bytes().enumerate().filter(|&(_, cell)| cell != b'.').map(|(index, _)| index).collect::<Vec<_>>();
+
+            let mut expected = route.iter().map(|&(x, y)| x + y * grid.width).collect::<Vec<_>>();
+            expected.sort_unstable();
+
+            assert_eq!(marked, expected);
+            assert_eq!(route.last(), Some(&grid.end));
+            assert_eq!(rendered.as_bytes()[grid.end.0 + grid.end.1 * grid.width], b'E');
+        }
+
+        let start = route1(&grid).unwrap()[0];
+        assert_eq!(start, grid.start);
+        assert_ne!(render_route(&grid, &[start, grid.end]).as_bytes()[0], b'.');
+    }
+
+    #[test]
+    fn ppm_has_header_and_route_colour() {
+        let grid = Grid::parse(EXAMPLE);
+        let route = route1(&grid).unwrap();
+        let image = render_ppm(&grid, &route, 2);
+
+        let header = b"P6\n16 10\n255\n";
+        assert_eq!(&image[..header.len()], header);
+        assert_eq!(image.len(), header.len() + 16 * 10 * 3);
+
+        let pixel = |x: usize, y: usize| {
+            let offset = header.len() + (x + y * 16) * 3;
+            (image[offset], image[offset + 1], image[offset + 2])
+        };
+
+        for &(x, y) in route.iter() {
+            assert_eq!(pixel(2 * x + 1, 2 * y + 1), ROUTE);
+        }
+
+        assert_eq!(pixel(4, 0), elevation_colour(grid.values[2]));
+    }
+}
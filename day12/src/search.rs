@@ -0,0 +1,138 @@
+use std::{cmp::Reverse, collections::{BinaryHeap, VecDeque}};
+
+use super::Grid;
+
+pub type Position = (usize, usize);
+
+/// A route through the grid and what it cost to walk it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub cost: usize,
+    /// Every position along the way, from the start to the goal.
+    pub positions: Vec<Position>,
+}
+
+impl Path {
+    pub fn steps(&self) -> usize {
+        self.positions.len() - 1
+    }
+}
+
+/// The puzzle's climbing rule: at most one higher than the current square, but any amount lower.
+pub fn climb_at_most_one(from: u8, to: u8) -> bool {
+    to <= from + 1
+}
+
+/// Every step costs the same, which makes the cost the number of steps.
+pub fn unit_cost(_from: u8, _to: u8) -> usize {
+    1
+}
+
+impl Grid {
+    pub fn position(&self, index: usize) -> Position {
+        (index % self.width, index / self.width)
+    }
+
+    pub fn index(&self, (x, y): Position) -> usize {
+        x + y * self.width
+    }
+
+    pub fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = self.position(index);
+
+        [(0, -1), (0, 1), (-1, 0), (1, 0)].into_iter().filter_map(move |(dx, dy)| {
+            let (x, y) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+            if x >= self.width || y >= self.height { return None }
+
+            Some(x + y * self.width)
+        })
+    }
+
+    /// Follows `parents` back from `goal` to where the search started.
+    fn trace_path(&self, parents: &[Option<usize>], goal: usize, cost: usize) -> Path {
+        let mut positions = vec![self.position(goal)];
+        let mut current = goal;
+
+        while let Some(parent) = parents[current] {
+            positions.push(self.position(parent));
+            current = parent;
+        }
+
+        positions.reverse();
+        Path { cost, positions }
+    }
+
+    /// Finds the path with the fewest steps from `start` to any position where `is_goal` holds, only
+    /// stepping from one height to another where `can_step` allows it.
+    pub fn bfs(&self, start: Position, is_goal: impl Fn(Position) -> bool, can_step: impl Fn(u8, u8) -> bool) -> Option<Path> {
+        let start = self.index(start);
+        let mut parents = vec![None; self.values.len()];
+        let mut steps = vec![usize::MAX; self.values.len()];
+        let mut queue = VecDeque::from([start]);
+
+        steps[start] = 0;
+
+        while let Some(current) = queue.pop_front() {
+            if is_goal(self.position(current)) { return Some(self.trace_path(&parents, current, steps[current])) }
+
+            for next in self.neighbours(current) {
+                if steps[next] != usize::MAX || !can_step(self.values[current], self.values[next]) { continue }
+
+                steps[next] = steps[current] + 1;
+                parents[next] = Some(current);
+                queue.push_back(next);
+            }
+        }
+
+        None
+    }
+
+    /// Finds the cheapest path from `start` to any position where `is_goal` holds, where stepping from
+    /// one height to another costs `cost`.
+    pub fn dijkstra(&self, start: Position, is_goal: impl Fn(Position) -> bool, can_step: impl Fn(u8, u8) -> bool, cost: impl Fn(u8, u8) -> usize) -> Option<Path> {
+        self.best_first(start, is_goal, can_step, cost, |_| 0)
+    }
+
+    /// Finds the cheapest path from `start` to `goal` like `dijkstra`, but searches towards the goal first.
+    /// The Manhattan distance it estimates the remaining cost with is only a lower bound, and so the path
+    /// only the cheapest, when every step costs at least 1.
+    pub fn a_star(&self, start: Position, goal: Position, can_step: impl Fn(u8, u8) -> bool, cost: impl Fn(u8, u8) -> usize) -> Option<Path> {
+        self.best_first(start, |position| position == goal, can_step, cost, |(x, y)| x.abs_diff(goal.0) + y.abs_diff(goal.1))
+    }
+
+    fn best_first(
+        &self,
+        start: Position,
+        is_goal: impl Fn(Position) -> bool,
+        can_step: impl Fn(u8, u8) -> bool,
+        cost: impl Fn(u8, u8) -> usize,
+        heuristic: impl Fn(Position) -> usize,
+    ) -> Option<Path> {
+        let start = self.index(start);
+        let mut parents = vec![None; self.values.len()];
+        let mut costs = vec![usize::MAX; self.values.len()];
+        let mut queue = BinaryHeap::from([Reverse((heuristic(self.position(start)), start))]);
+
+        costs[start] = 0;
+
+        while let Some(Reverse((estimate, current))) = queue.pop() {
+            // A cheaper way here was found after this entry was queued.
+            if estimate > costs[current] + heuristic(self.position(current)) { continue }
+
+            if is_goal(self.position(current)) { return Some(self.trace_path(&parents, current, costs[current])) }
+
+            for next in self.neighbours(current) {
+                if !can_step(self.values[current], self.values[next]) { continue }
+
+                let next_cost = costs[current] + cost(self.values[current], self.values[next]);
+                if next_cost >= costs[next] { continue }
+
+                costs[next] = next_cost;
+                parents[next] = Some(current);
+                queue.push(Reverse((next_cost + heuristic(self.position(next)), next)));
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,189 @@
+use std::{cmp::Ordering, fmt::{self, Display, Formatter}};
+
+use super::Entry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Side::Left  => write!(f, "left"),
+            Side::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// Why a comparison was decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Smaller(Side),
+    RanOut(Side),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepKind {
+    Compare(Entry, Entry),
+    /// An integer was compared with a list, so it was wrapped in a list of its own.
+    Promote(Side, Entry),
+    Decide(Reason),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The indices taken into both packets to get to the entries being compared. Once an integer has been
+    /// promoted, the path goes on through index 0 of the list it was wrapped in, which neither packet has.
+    pub path: Vec<usize>,
+    /// How deeply the step is nested in the comparison, which grows faster than `path` when an integer is promoted.
+    pub depth: usize,
+    pub kind: StepKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub ordering: Ordering,
+    pub steps: Vec<Step>,
+}
+
+impl Explanation {
+    /// The indices leading to the element that decided the comparison, if anything did.
+    pub fn deciding_path(&self) -> Option<&[usize]> {
+        self.steps.last().filter(|step| matches!(step.kind, StepKind::Decide(_))).map(|step| step.path.as_slice())
+    }
+}
+
+fn explain(left: &Entry, right: &Entry, path: &mut Vec<usize>, depth: usize, steps: &mut Vec<Step>) -> Ordering {
+    let mut step = |kind, depth| steps.push(Step { path: path.clone(), depth, kind });
+    step(StepKind::Compare(left.clone(), right.clone()), depth);
+
+    let ordering = match (left, right) {
+        (Entry::Value(l), Entry::Value(r)) => l.cmp(r),
+        (Entry::Value(l), Entry::List(_)) => {
+            let promoted = Entry::List(vec![Entry::Value(*l)]);
+            step(StepKind::Promote(Side::Left, promoted.clone()), depth + 1);
+            return explain(&promoted, right, path, depth + 1, steps);
+        },
+        (Entry::List(_), Entry::Value(r)) => {
+            let promoted = Entry::List(vec![Entry::Value(*r)]);
+            step(StepKind::Promote(Side::Right, promoted.clone()), depth + 1);
+            return explain(left, &promoted, path, depth + 1, steps);
+        },
+        (Entry::List(l), Entry::List(r)) => {
+            for (index, (l, r)) in l.iter().zip(r).enumerate() {
+                path.push(index);
+                let ordering = explain(l, r, path, depth + 1, steps);
+                path.pop();
+
+                if ordering.is_ne() { return ordering }
+            }
+
+            let ordering = l.len().cmp(&r.len());
+            let side = if ordering.is_lt() { Side::Left } else { Side::Right };
+            if ordering.is_ne() {
+                steps.push(Step { path: path.clone(), depth: depth + 1, kind: StepKind::Decide(Reason::RanOut(side)) });
+            }
+
+            return ordering;
+        },
+    };
+
+    if ordering.is_ne() {
+        let side = if ordering.is_lt() { Side::Left } else { Side::Right };
+        steps.push(Step { path: path.clone(), depth: depth + 1, kind: StepKind::Decide(Reason::Smaller(side)) });
+    }
+
+    ordering
+}
+
+impl Entry {
+    /// Compares the packets like `cmp`, but also records every step taken along the way.
+    pub fn compare_explain(&self, other: &Entry) -> Explanation {
+        let mut steps = vec![];
+        let ordering = explain(self, other, &mut vec![], 0, &mut steps);
+
+        Explanation { ordering, steps }
+    }
+}
+
+impl Display for Explanation {
+    /// Writes the steps the way the puzzle statement walks through its examples.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 { writeln!(f)? }
+            write!(f, "{}- ", "  ".repeat(step.depth))?;
+
+            match &step.kind {
+                StepKind::Compare(left, right) => write!(f, "Compare {} vs {}", left, right)?,
+                StepKind::Promote(side, promoted) => write!(f, "Mixed types; convert {} to {} and retry comparison", side, promoted)?,
+                StepKind::Decide(reason) => {
+                    let side = match reason {
+                        Reason::Smaller(side) | Reason::RanOut(side) => side,
+                    };
+
+                    let (capitalised, verdict) = match side {
+                        Side::Left  => ("Left", "in the right order"),
+                        Side::Right => ("Right", "not in the right order"),
+                    };
+
+                    match reason {
+                        Reason::Smaller(_)  => write!(f, "{} side is smaller, so inputs are {}", capitalised, verdict)?,
+                        Reason::RanOut(_)   => write!(f, "{} side ran out of items, so inputs are {}", capitalised, verdict)?,
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verdicts_match_cmp() {
+        for input in [crate::tests::EXAMPLE, include_str!("input.txt")] {
+            for (left, right) in Entry::parse(input).unwrap() {
+                for (left, right) in [(&left, &right), (&right, &left), (&left, &left)] {
+                    let explanation = left.compare_explain(right);
+
+                    assert_eq!(explanation.ordering, left.cmp(right), "{} vs {}", left, right);
+                    assert_eq!(explanation.deciding_path().is_some(), explanation.ordering.is_ne(), "{} vs {}", left, right);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn example_verdicts() {
+        let in_order = Entry::parse(crate::tests::EXAMPLE).unwrap().iter()
+            .map(|(left, right)| left.compare_explain(right).ordering.is_lt())
+            .collect::<Vec<_>>();
+
+        assert_eq!(in_order, [true, true, false, true, false, true, false, false]);
+    }
+
+    #[test]
+    fn example_pair_is_explained_like_the_puzzle() {
+        let pairs = Entry::parse(crate::tests::EXAMPLE).unwrap();
+        let (left, right) = &pairs[1];
+        let explanation = left.compare_explain(right);
+
+        assert_eq!(explanation.to_string(), "\
+- Compare [[1],[2,3,4]] vs [[1],4]
+  - Compare [1] vs [1]
+    - Compare 1 vs 1
+  - Compare [2,3,4] vs 4
+    - Mixed types; convert right to [4] and retry comparison
+    - Compare [2,3,4] vs [4]
+      - Compare 2 vs 4
+        - Left side is smaller, so inputs are in the right order");
+
+        // The 4 was promoted, so the deciding path continues into the list it was wrapped in.
+        assert_eq!(explanation.deciding_path(), Some([1, 0].as_slice()));
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::Value;
+
+use super::Entry;
+
+/// A JSON value that is not a packet, with only arrays and non-negative integers allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    pub value: Value,
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} is neither a list nor a non-negative integer", self.value)
+    }
+}
+
+impl From<&Entry> for Value {
+    fn from(entry: &Entry) -> Value {
+        match entry {
+            Entry::Value(value) => Value::from(*value),
+            Entry::List(items)  => Value::Array(items.iter().map(Value::from).collect()),
+        }
+    }
+}
+
+impl TryFrom<&Value> for Entry {
+    type Error = ConversionError;
+
+    fn try_from(value: &Value) -> Result<Entry, ConversionError> {
+        match value {
+            Value::Array(items) => Ok(Entry::List(items.iter().map(Entry::try_from).collect::<Result<_, _>>()?)),
+            Value::Number(number) => number.as_u64()
+                .and_then(|x| usize::try_from(x).ok())
+                .map(Entry::Value)
+                .ok_or_else(|| ConversionError { value: value.clone() }),
+            _ => Err(ConversionError { value: value.clone() }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    /// `Entry`'s equality compares packets like the puzzle does, under which `[[1]]` equals `[1]`,
+    /// so round trips are checked on the text instead.
+    #[test]
+    fn packets_round_trip_through_display_and_json() {
+        for input in [crate::tests::EXAMPLE, include_str!("input.txt")] {
+            for line in input.lines().filter(|line| !line.is_empty()) {
+                let packet = Entry::from_str(line).unwrap();
+                assert_eq!(packet.to_string(), line);
+
+                let json = Value::from(&packet);
+                assert_eq!(json.to_string(), line);
+                assert_eq!(serde_json::from_str::<Value>(line).unwrap(), json);
+                assert_eq!(Entry::try_from(&json).unwrap().to_string(), line);
+            }
+        }
+    }
+
+    #[test]
+    fn nesting_survives_the_round_trip() {
+        for line in ["[[1]]", "[1]", "[[[]]]", "[[]]"] {
+            let json = Value::from(&Entry::from_str(line).unwrap());
+            assert_eq!(Entry::try_from(&json).unwrap().to_string(), line);
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::{collections::VecDeque, cmp::Ordering};
+mod explain;
+mod json;
+
+use std::{env, fs, io, cmp::Ordering, fmt::{self, Display, Formatter}, str::FromStr};
 
 #[derive(Debug, Clone, Eq)]
 enum Entry {
@@ -30,72 +33,151 @@ impl Ord for Entry {
     }
 }
 
-impl Entry {
-    fn parse_entry(s: &str) -> Entry {
-        let mut tokens = s.chars().collect::<VecDeque<char>>();
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ParseError {
+    pub line: usize,
+    /// The byte offset within the line, counting from one.
+    pub column: usize,
+    pub message: String,
+}
 
-        Entry::parse_list(&mut tokens)
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
     }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
 
-    fn parse_number(tokens: &mut VecDeque<char>) -> Entry {
-        let mut number = String::new();
+impl Parser<'_> {
+    fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        Err(ParseError { line: 1, column: self.position + 1, message: String::from(message) })
+    }
 
-        while let Some(current_token) = tokens.front() {
+    fn expect(&mut self, byte: u8) -> Result<(), ParseError> {
+        match self.input.get(self.position) {
+            Some(&x) if x == byte => { self.position += 1; Ok(()) },
+            Some(_) => self.error(&format!("expected '{}'", byte as char)),
+            None    => self.error(&format!("expected '{}' but the packet ended", byte as char)),
+        }
+    }
 
-            if current_token.is_numeric() {
-                number.push(*current_token);
-                tokens.pop_front();
-            } else {
-                break;
-            }
+    fn entry(&mut self) -> Result<Entry, ParseError> {
+        match self.input.get(self.position) {
+            Some(b'[') => self.list(),
+            Some(byte) if byte.is_ascii_digit() => self.number(),
+            Some(_) => self.error("expected a number or '['"),
+            None    => self.error("expected a number or '[' but the packet ended"),
+        }
+    }
+
+    fn number(&mut self) -> Result<Entry, ParseError> {
+        let start = self.position;
+        while self.input.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
         }
 
-        Entry::Value(number.parse::<usize>().unwrap())
+        let digits = std::str::from_utf8(&self.input[start..self.position]).unwrap();
+        match digits.parse() {
+            Ok(value) => Ok(Entry::Value(value)),
+            Err(_) => { self.position = start; self.error("number too large") },
+        }
     }
 
-    fn parse_list(tokens: &mut VecDeque<char>) -> Entry {
+    fn list(&mut self) -> Result<Entry, ParseError> {
+        self.expect(b'[')?;
         let mut items = Vec::new();
 
-        tokens.pop_front();
-
-        while let Some(current_token) = tokens.front() {
-            if current_token.is_numeric() {
-                items.push(Entry::parse_number(tokens));
-            } else
-            if *current_token == '[' {
-                items.push(Entry::parse_list(tokens));
-            } else 
-            if *current_token == ',' {
-                tokens.pop_front();
-            } else
-            if  *current_token == ']' {
-                tokens.pop_front();
-                break;
+        if self.input.get(self.position) == Some(&b']') {
+            self.position += 1;
+            return Ok(Entry::List(items));
+        }
+
+        loop {
+            items.push(self.entry()?);
+
+            match self.input.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b']') => { self.position += 1; return Ok(Entry::List(items)) },
+                Some(_) => return self.error("expected ',' or ']'"),
+                None    => return self.error("expected ',' or ']' but the packet ended"),
             }
         }
+    }
+}
+
+impl FromStr for Entry {
+    type Err = ParseError;
 
-        Entry::List(items)
+    /// Reads a packet exactly as the puzzle writes it: a list of numbers and lists, separated by commas
+    /// without any whitespace.
+    fn from_str(s: &str) -> Result<Entry, ParseError> {
+        let mut parser = Parser { input: s.as_bytes(), position: 0 };
+        let entry = parser.list()?;
+
+        if parser.position < parser.input.len() { return parser.error("unexpected character after the packet") }
+
+        Ok(entry)
     }
+}
+
+impl Display for Entry {
+    /// Writes the packet back the way the puzzle input has it, so that parsing it gives the same packet.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Value(value) => write!(f, "{}", value),
+            Entry::List(items)  => {
+                write!(f, "[")?;
+
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 { write!(f, ",")? }
+                    write!(f, "{}", item)?;
+                }
 
-    fn parse(s: &str) -> Vec<(Entry, Entry)> {
-        let mut entries = Vec::<(Entry, Entry)>::new();
-        let pairs = s.split("\n\n").collect::<Vec<_>>();
+                write!(f, "]")
+            }
+        }
+    }
+}
 
-        pairs.iter().for_each(|pair| {
-            let lines = pair.split('\n').take(2).collect::<Vec<&str>>();
+impl Entry {
+    /// Reads pairs of packets, one packet per line and a blank line between pairs.
+    fn parse(s: &str) -> Result<Vec<(Entry, Entry)>, ParseError> {
+        let mut pairs = Vec::new();
+        let mut pair = Vec::new();
+        let lines = s.lines().collect::<Vec<_>>();
 
-            entries.push((
-                Entry::parse_entry(lines[0]),
-                Entry::parse_entry(lines[1])
-            ))
-        });
+        for (index, line) in lines.iter().enumerate() {
+            let error = |message: &str| ParseError { line: index + 1, column: 1, message: String::from(message) };
+
+            if line.is_empty() {
+                if pair.len() == 1 { return Err(error("expected the second packet of the pair")) }
+                continue;
+            }
+
+            if pair.len() == 2 { return Err(error("expected a blank line between pairs")) }
+
+            pair.push(line.parse::<Entry>().map_err(|error| ParseError { line: index + 1, ..error })?);
+
+            if pair.len() == 2 && lines.get(index + 1).is_none_or(|line| line.is_empty()) {
+                let right = pair.pop().unwrap();
+                pairs.push((pair.pop().unwrap(), right));
+            }
+        }
+
+        if !pair.is_empty() {
+            return Err(ParseError { line: lines.len() + 1, column: 1, message: String::from("expected the second packet of the pair") });
+        }
 
-        entries
+        Ok(pairs)
     }
 }
 
 fn part1(input: &str) -> usize {
-    let entries = Entry::parse(input);
+    let entries = Entry::parse(input).expect("Invalid puzzle input");
 
     entries.iter().enumerate().map(|(index, (left, right))| {
         let in_order = left < right;
@@ -106,7 +188,7 @@ fn part1(input: &str) -> usize {
 }
 
 fn part2(input: &str) -> usize {
-    let mut entries = Entry::parse(input).into_iter().fold(vec![], |acc, (l, r)| {
+    let mut entries = Entry::parse(input).expect("Invalid puzzle input").into_iter().fold(vec![], |acc, (l, r)| {
         let mut acc = acc;
         acc.push(l);
         acc.push(r);
@@ -127,9 +209,130 @@ fn part2(input: &str) -> usize {
     key_part1 * key_part2
 }
 
+fn invalid_data(error: impl Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+fn read_pairs(path: Option<&str>) -> io::Result<Vec<(Entry, Entry)>> {
+    let input = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => String::from(include_str!("input.txt")),
+    };
+
+    Entry::parse(&input).map_err(invalid_data)
+}
+
+/// Prints the pairs as a JSON array of `[left, right]` arrays.
+fn to_json(path: Option<&str>) -> io::Result<()> {
+    let pairs = read_pairs(path)?;
+    let json = serde_json::Value::Array(pairs.iter()
+        .map(|(left, right)| serde_json::Value::Array(vec![left.into(), right.into()]))
+        .collect());
+
+    println!("{}", json);
+    Ok(())
+}
+
+/// Reads pairs written by `to_json` and prints them the way the puzzle input has them.
+fn from_json(path: &str) -> io::Result<()> {
+    let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?).map_err(invalid_data)?;
+
+    let pairs = match &json {
+        serde_json::Value::Array(pairs) => pairs,
+        _ => return Err(invalid_data("expected an array of pairs")),
+    };
+
+    for (index, pair) in pairs.iter().enumerate() {
+        let [left, right] = pair.as_array().map(Vec::as_slice).unwrap_or_default() else {
+            return Err(invalid_data(format!("pair {} is not an array of two packets", index + 1)));
+        };
+
+        if index > 0 { println!() }
+        println!("{}", Entry::try_from(left).map_err(invalid_data)?);
+        println!("{}", Entry::try_from(right).map_err(invalid_data)?);
+    }
+
+    Ok(())
+}
+
+/// Prints how every pair was compared, the way the puzzle statement explains its examples.
+fn explain(path: Option<&str>) -> io::Result<()> {
+    for (index, (left, right)) in read_pairs(path)?.iter().enumerate() {
+        if index > 0 { println!() }
+
+        println!("== Pair {} ==", index + 1);
+        let explanation = left.compare_explain(right);
+        println!("{}", explanation);
+
+        if let Some(path) = explanation.deciding_path() {
+            let indices = path.iter().map(usize::to_string).collect::<Vec<_>>().join(", ");
+            println!("Decided at index path [{}]", indices);
+        }
+    }
+
+    Ok(())
+}
+
 fn main() {
     let input = include_str!("input.txt");
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["check", path]     => Some(read_pairs(Some(path)).map(|pairs| println!("{} pairs", pairs.len()))),
+        ["to-json"]         => Some(to_json(None)),
+        ["to-json", path]   => Some(to_json(Some(path))),
+        ["from-json", path] => Some(from_json(path)),
+        ["explain"]         => Some(explain(None)),
+        ["explain", path]   => Some(explain(Some(path))),
+        _                   => None,
+    };
+
+    if let Some(result) = command {
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
 
     println!("part1: {}", part1(input));
     println!("part2: {}", part2(input));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub const EXAMPLE: &str = "\
+[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]
+";
+
+    #[test]
+    fn example() {
+        assert_eq!(part1(EXAMPLE), 13);
+        assert_eq!(part2(EXAMPLE), 140);
+    }
+}
@@ -0,0 +1,188 @@
+use std::{fs, io::{self, Write}, path::Path};
+
+use super::Directory;
+
+const BLOCK_SIZE: usize = 512;
+
+fn validate_name(name: &str) -> io::Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') || name.contains('\\') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("refusing to export entry named '{}'", name)));
+    }
+
+    Ok(())
+}
+
+/// Recreates `directory` below `root`, writing every file as a sparse file of the listed size.
+pub fn export_to_directory(directory: &Directory, root: &Path) -> io::Result<()> {
+    fs::create_dir_all(root)?;
+
+    for file in directory.files.iter() {
+        validate_name(&file.name)?;
+        fs::File::create(root.join(&file.name))?.set_len(file.size as u64)?;
+    }
+
+    for subdirectory in directory.subdirectories.iter() {
+        let subdirectory = subdirectory.borrow();
+        validate_name(&subdirectory.name)?;
+        export_to_directory(&subdirectory, &root.join(&subdirectory.name))?;
+    }
+
+    Ok(())
+}
+
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+
+    for c in value.chars() {
+        match c {
+            '"'     => result.push_str("\\\""),
+            '\\'    => result.push_str("\\\\"),
+            '\n'    => result.push_str("\\n"),
+            '\t'    => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c       => result.push(c),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+fn write_json_directory(directory: &Directory, name: &str, writer: &mut impl Write, indent: usize) -> io::Result<()> {
+    let padding = "  ".repeat(indent);
+
+    writeln!(writer, "{}{{", padding)?;
+    writeln!(writer, "{}  \"name\": {},", padding, json_string(name))?;
+    writeln!(writer, "{}  \"type\": \"directory\",", padding)?;
+    writeln!(writer, "{}  \"size\": {},", padding, directory.total_size())?;
+    write!(writer, "{}  \"children\": [", padding)?;
+
+    let mut first = true;
+
+    for file in directory.files.iter() {
+        write!(writer, "{}\n{}    {{ \"name\": {}, \"type\": \"file\", \"size\": {} }}",
+            if first { "" } else { "," }, padding, json_string(&file.name), file.size)?;
+        first = false;
+    }
+
+    for subdirectory in directory.subdirectories.iter() {
+        let subdirectory = subdirectory.borrow();
+        writeln!(writer, "{}", if first { "" } else { "," })?;
+        write_json_directory(&subdirectory, &subdirectory.name, writer, indent + 2)?;
+        first = false;
+    }
+
+    if first { writeln!(writer, "]")? } else { write!(writer, "\n{}  ]\n", padding)? }
+    write!(writer, "{}}}", padding)
+}
+
+/// Writes the tree as a JSON document of nested directory and file objects.
+pub fn export_json(directory: &Directory, writer: &mut impl Write) -> io::Result<()> {
+    write_json_directory(directory, "/", writer, 0)?;
+    writeln!(writer)
+}
+
+fn write_octal(field: &mut [u8], value: u64) -> io::Result<()> {
+    let digits = format!("{:0width$o}", value, width = field.len() - 1);
+
+    if digits.len() >= field.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} does not fit a {} byte tar field", value, field.len())));
+    }
+
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    Ok(())
+}
+
+/// Writes a file size, falling back to the GNU base-256 encoding for sizes of 8 GiB and up, which
+/// have more octal digits than the field can hold.
+fn write_size(field: &mut [u8; 12], size: u64) {
+    if write_octal(field, size).is_ok() { return }
+
+    field.fill(0);
+    field[0] = 0x80;
+    field[4..].copy_from_slice(&size.to_be_bytes());
+}
+
+fn tar_header(path: &str, size: u64, is_directory: bool) -> io::Result<[u8; BLOCK_SIZE]> {
+    let mut header = [0u8; BLOCK_SIZE];
+
+    let (prefix, name) = match path.len() {
+        0..=100 => ("", path),
+        _ => path.char_indices()
+            .filter(|&(index, c)| c == '/' && index <= 155 && index + 1 < path.len() && path.len() - index - 1 <= 100)
+            .map(|(index, _)| (&path[..index], &path[index + 1..]))
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("path too long for tar: '{}'", path)))?,
+    };
+
+    header[0..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], if is_directory { 0o755 } else { 0o644 })?;
+    write_octal(&mut header[108..116], 0)?;
+    write_octal(&mut header[116..124], 0)?;
+    write_size((&mut header[124..136]).try_into().unwrap(), size);
+    write_octal(&mut header[136..148], 0)?;
+    header[156] = if is_directory { b'5' } else { b'0' };
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    header[148..156].copy_from_slice(b"        ");
+    let checksum = header.iter().map(|&x| x as u64).sum::<u64>();
+    write_octal(&mut header[148..155], checksum)?;
+    header[155] = b' ';
+
+    Ok(header)
+}
+
+fn write_tar_directory(directory: &Directory, path: &str, writer: &mut impl Write) -> io::Result<()> {
+    let zeroes = [0u8; BLOCK_SIZE];
+
+    for file in directory.files.iter() {
+        validate_name(&file.name)?;
+
+        writer.write_all(&tar_header(&format!("{}{}", path, file.name), file.size as u64, false)?)?;
+
+        let mut remaining = file.size.div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+        while remaining > 0 {
+            writer.write_all(&zeroes)?;
+            remaining -= BLOCK_SIZE;
+        }
+    }
+
+    for subdirectory in directory.subdirectories.iter() {
+        let subdirectory = subdirectory.borrow();
+        validate_name(&subdirectory.name)?;
+
+        let path = format!("{}{}/", path, subdirectory.name);
+        writer.write_all(&tar_header(&path, 0, true)?)?;
+        write_tar_directory(&subdirectory, &path, writer)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the tree as a ustar archive, with every file filled with zeroes up to its size.
+pub fn export_tar(directory: &Directory, writer: &mut impl Write) -> io::Result<()> {
+    write_tar_directory(directory, "", writer)?;
+    writer.write_all(&[0u8; BLOCK_SIZE * 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tar_header_encodes_large_sizes_in_base_256() {
+        let size = 8 << 30;
+        let header = tar_header("big", size, false).unwrap();
+
+        assert_eq!(header[124], 0x80);
+        assert_eq!(header[128..136], size.to_be_bytes());
+    }
+
+    #[test]
+    fn tar_header_uses_octal_below_8_gib() {
+        let header = tar_header("small", (8 << 30) - 1, false).unwrap();
+        assert_eq!(&header[124..136], b"77777777777\0");
+    }
+}
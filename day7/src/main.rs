@@ -1,7 +1,10 @@
-use std::{rc::{Weak, Rc}, cell::RefCell, str::FromStr};
+mod export;
+
+use std::{rc::{Weak, Rc}, cell::RefCell, env, io::{self, BufWriter, Write}, path::PathBuf, str::FromStr, fmt::{self, Display, Formatter}};
 
 #[derive(Debug)]
 struct File {
+    pub name: String,
     pub size: usize,
 }
 
@@ -22,6 +25,78 @@ impl Directory {
         
         sum
     }
+
+    fn subdirectory(&self, name: &str) -> Option<&Rc<RefCell<Directory>>> {
+        self.subdirectories.iter().find(|&dir| dir.borrow().name == name)
+    }
+
+    fn file(&self, name: &str) -> Option<&File> {
+        self.files.iter().find(|file| file.name == name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ParseErrorKind {
+    AboveRoot,
+    NotADirectory(String),
+    NotAFile(String),
+    ConflictingFileSize { name: String, previous: usize, current: usize },
+    MissingArgument(String),
+    OutputWithoutListing(String),
+    InvalidListing(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseError {
+    pub line: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ParseErrorKind::AboveRoot                   => write!(f, "cannot move above the root directory"),
+            ParseErrorKind::NotADirectory(name)         => write!(f, "'{}' is a file, not a directory", name),
+            ParseErrorKind::NotAFile(name)              => write!(f, "'{}' is a directory, not a file", name),
+            ParseErrorKind::ConflictingFileSize { name, previous, current } =>
+                write!(f, "'{}' was listed with size {} before, now {}", name, previous, current),
+            ParseErrorKind::MissingArgument(command)    => write!(f, "'{}' is missing an argument", command),
+            ParseErrorKind::OutputWithoutListing(line)  => write!(f, "'{}' is not preceded by ls", line),
+            ParseErrorKind::InvalidListing(line)        => write!(f, "'{}' is not a valid ls entry", line),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ParseWarningKind {
+    UnknownCommand(String),
+    UnlistedDirectory(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ParseWarning {
+    pub line: usize,
+    pub kind: ParseWarningKind,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            ParseWarningKind::UnknownCommand(command)   => write!(f, "ignoring unknown command '{}'", command),
+            ParseWarningKind::UnlistedDirectory(name)   => write!(f, "entered '{}' before it was listed", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputMode {
+    None,
+    Listing,
+    Ignoring,
 }
 
 #[derive(Debug)]
@@ -44,130 +119,607 @@ impl FileSystem {
         FileSystem { root, current_node }
     }
 
+    fn parse(input: &str) -> Result<(FileSystem, Vec<ParseWarning>), ParseError> {
+        let mut fs = FileSystem::new();
+        let mut warnings = vec![];
+        let mut mode = OutputMode::None;
+
+        for (index, line) in input.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |kind| ParseError { line: line_number, kind };
+
+            let line = line.trim();
+            if line.is_empty() { continue }
+
+            if let Some(command) = line.strip_prefix('$') {
+                let mut parts = command.split_whitespace();
+
+                mode = match parts.next() {
+                    Some("cd") => {
+                        let path = parts.next().ok_or_else(|| error(ParseErrorKind::MissingArgument(String::from("cd"))))?;
+
+                        fs.change_directory(path, |name| warnings.push(ParseWarning {
+                            line: line_number,
+                            kind: ParseWarningKind::UnlistedDirectory(name),
+                        })).map_err(error)?;
+
+                        OutputMode::None
+                    },
+                    Some("ls") => OutputMode::Listing,
+                    _ => {
+                        warnings.push(ParseWarning {
+                            line: line_number,
+                            kind: ParseWarningKind::UnknownCommand(command.trim().to_string()),
+                        });
+
+                        OutputMode::Ignoring
+                    }
+                };
+
+                continue;
+            }
+
+            match mode {
+                OutputMode::None        => return Err(error(ParseErrorKind::OutputWithoutListing(line.to_string()))),
+                OutputMode::Ignoring    => continue,
+                OutputMode::Listing     => {},
+            }
+
+            let (kind, name) = line.split_once(char::is_whitespace)
+                .map(|(kind, name)| (kind, name.trim()))
+                .ok_or_else(|| error(ParseErrorKind::InvalidListing(line.to_string())))?;
+
+            if kind == "dir" {
+                fs.create_directory(name).map_err(error)?;
+            } else {
+                let size = kind.parse().map_err(|_| error(ParseErrorKind::InvalidListing(line.to_string())))?;
+                fs.create_file(name, size).map_err(error)?;
+            }
+        }
+
+        fs.go_to_root();
+
+        Ok((fs, warnings))
+    }
+
     fn go_to_root(&mut self) {
         self.current_node = Rc::downgrade(&self.root);
     }
 
-    fn go_up_directory(&mut self) {
+    fn go_up_directory(&mut self) -> Result<(), ParseErrorKind> {
         let node = self.current_node.upgrade().unwrap();
         let directory = node.borrow();
 
-        let parent = directory.parent.as_ref().expect("Current node has no parent node");
+        let parent = directory.parent.as_ref().ok_or(ParseErrorKind::AboveRoot)?;
 
         self.current_node = Rc::downgrade(&parent.upgrade().unwrap());
+
+        Ok(())
     }
 
-    fn go_to_directory(&mut self, directory_name: &str) {
+    fn go_to_directory(&mut self, directory_name: &str, on_unlisted: &mut impl FnMut(String)) -> Result<(), ParseErrorKind> {
         let node = self.current_node.upgrade().unwrap();
-        let directory = node.borrow();
 
-        let destination = directory.subdirectories.iter()
-            .find(|&dir| dir.borrow().name == directory_name)
-            .expect("No node found with the given name");
+        if node.borrow().subdirectory(directory_name).is_none() {
+            self.create_directory(directory_name)?;
+            on_unlisted(String::from(directory_name));
+        }
+
+        let directory = node.borrow();
+        let destination = directory.subdirectory(directory_name).unwrap();
 
         self.current_node = Rc::downgrade(destination);
+
+        Ok(())
+    }
+
+    fn change_directory(&mut self, path: &str, mut on_unlisted: impl FnMut(String)) -> Result<(), ParseErrorKind> {
+        if path.starts_with('/') { self.go_to_root() }
+
+        for segment in path.split('/') {
+            match segment {
+                "" | "."    => continue,
+                ".."        => self.go_up_directory()?,
+                _           => self.go_to_directory(segment, &mut on_unlisted)?,
+            }
+        }
+
+        Ok(())
     }
 
-    fn create_directory(&mut self, directory_name: &str) {
+    fn create_directory(&mut self, directory_name: &str) -> Result<(), ParseErrorKind> {
         let node = self.current_node.upgrade().unwrap();
-        
-        node.borrow_mut().subdirectories.push(Rc::new(RefCell::new(Directory {
+        let mut directory = node.borrow_mut();
+
+        if directory.file(directory_name).is_some() {
+            return Err(ParseErrorKind::NotADirectory(String::from(directory_name)));
+        }
+
+        if directory.subdirectory(directory_name).is_some() { return Ok(()) }
+
+        directory.subdirectories.push(Rc::new(RefCell::new(Directory {
             parent: Some(Rc::downgrade(&node)),
             name: String::from(directory_name),
             files: vec![],
             subdirectories: vec![],
         })));
+
+        Ok(())
     }
 
-    fn create_file(&mut self, size: usize) {
+    fn create_file(&mut self, file_name: &str, size: usize) -> Result<(), ParseErrorKind> {
         let node = self.current_node.upgrade().unwrap();
-        
-        node.borrow_mut().files.push(File { size });
+        let mut directory = node.borrow_mut();
+
+        if directory.subdirectory(file_name).is_some() {
+            return Err(ParseErrorKind::NotAFile(String::from(file_name)));
+        }
+
+        match directory.file(file_name) {
+            Some(file) if file.size != size => Err(ParseErrorKind::ConflictingFileSize {
+                name: String::from(file_name),
+                previous: file.size,
+                current: size,
+            }),
+            Some(_) => Ok(()),
+            None => {
+                directory.files.push(File { name: String::from(file_name), size });
+                Ok(())
+            }
+        }
     }
 }
 
 impl FromStr for FileSystem {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let mut fs = FileSystem::new();
-
-        for line in input.split_terminator('\n') {
-            let parts = line.split(' ').collect::<Vec<_>>();
-    
-            let command_parts = (
-                *parts.get(0).unwrap_or(&""),
-                *parts.get(1).unwrap_or(&""),
-                *parts.get(2).unwrap_or(&""),
-            );
-    
-            match command_parts {
-                ("$", "cd", "/")    => fs.go_to_root(),
-                ("$", "cd", "..")   => fs.go_up_directory(),
-                ("$", "cd", _)      => fs.go_to_directory(command_parts.2),
-                ("$", "ls", _)      => continue,
-                ("dir", _, _)       => fs.create_directory(command_parts.1),
-                (_, _, _)           => fs.create_file(command_parts.0.parse().unwrap())
-            }
-        }
-    
-        Ok(fs)
+        FileSystem::parse(input).map(|(fs, _)| fs)
     }
 }
 
-fn find_clearable_space(directory: &Directory) -> usize {
+fn find_clearable_space(directory: &Directory, threshold: usize) -> usize {
     let mut sum = 0;
     let current_directory_size = directory.total_size();
 
     sum += directory.subdirectories.iter()
-        .map(|x| find_clearable_space(&x.as_ref().borrow()))
+        .map(|x| find_clearable_space(&x.as_ref().borrow(), threshold))
         .sum::<usize>();
 
-    if current_directory_size <= 100_000 {
+    if current_directory_size <= threshold {
         sum += current_directory_size;
     }
 
     sum
 }
 
+const CLEARABLE_THRESHOLD: usize    = 100_000;
 const AVAILABLE_DISK_SPACE: usize   = 70000000;
 const REQUIRED_UNUSED_SPACE: usize  = 30000000;
 
-fn find_update_space(directory: &Directory, required: &usize) -> Option<usize> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DirectoryEntry {
+    pub path: String,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CleanupStrategy {
+    /// Delete the smallest directory that frees enough space on its own.
+    SingleDirectory,
+    /// Delete the set of non-nested directories that frees the least space
+    /// while still freeing enough.
+    MinimalSet,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CleanupPlan {
+    pub required: usize,
+    pub directories: Vec<DirectoryEntry>,
+}
+
+impl CleanupPlan {
+    fn freed(&self) -> usize {
+        self.directories.iter().map(|x| x.size).sum()
+    }
+}
+
+fn child_path(path: &str, name: &str) -> String {
+    if path.ends_with('/') { format!("{}{}", path, name) } else { format!("{}/{}", path, name) }
+}
+
+fn find_update_space(directory: &Directory, path: &str, required: usize) -> Option<DirectoryEntry> {
     let current_directory = directory.total_size();
-    if current_directory < *required { return None }
+    if current_directory < required { return None }
+
+    let lowest = directory.subdirectories.iter()
+        .filter_map(|x| {
+            let subdirectory = x.as_ref().borrow();
+            find_update_space(&subdirectory, &child_path(path, &subdirectory.name), required)
+        })
+        .min_by_key(|x| x.size);
+
+    lowest.or_else(|| Some(DirectoryEntry { path: String::from(path), size: current_directory }))
+}
+
+/// Lists every directory in pre-order, paired with the index just past its
+/// subtree so that a whole subtree can be skipped in one step.
+fn flatten_directories(directory: &Directory, path: &str, entries: &mut Vec<(DirectoryEntry, usize)>) {
+    let index = entries.len();
+    entries.push((DirectoryEntry { path: String::from(path), size: directory.total_size() }, 0));
+
+    for subdirectory in directory.subdirectories.iter() {
+        let subdirectory = subdirectory.as_ref().borrow();
+        flatten_directories(&subdirectory, &child_path(path, &subdirectory.name), entries);
+    }
+
+    entries[index].1 = entries.len();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CleanupError {
+    /// Not even deleting everything frees enough space.
+    NotEnoughSpace,
+    /// Searching for the minimal set would need more memory than `MAX_SIZE_SET_MEMORY`.
+    SearchTooLarge { directories: usize, upper_bound: usize },
+}
+
+impl Display for CleanupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CleanupError::NotEnoughSpace => write!(f, "no directories free enough space"),
+            CleanupError::SearchTooLarge { directories, upper_bound } =>
+                write!(f, "searching {} directories for up to {} freed bytes would need more than {} bytes of memory",
+                    directories, upper_bound, MAX_SIZE_SET_MEMORY),
+        }
+    }
+}
+
+/// The most memory `find_update_set` may use for its size sets.
+const MAX_SIZE_SET_MEMORY: usize = 64 << 20;
+
+#[derive(Clone)]
+struct SizeSet {
+    words: Vec<u64>,
+    limit: usize,
+}
+
+impl SizeSet {
+    fn new(limit: usize) -> SizeSet {
+        SizeSet { words: vec![0; limit / 64 + 1], limit }
+    }
 
-    let mut lowest = directory.subdirectories.iter()
-        .map(|x| find_update_space(&x.as_ref().borrow(), required))
-        .filter(|x| x.is_some())
-        .collect::<Vec<Option<usize>>>();
+    fn contains(&self, size: usize) -> bool {
+        size <= self.limit && self.words[size / 64] & (1 << (size % 64)) != 0
+    }
+
+    fn insert(&mut self, size: usize) {
+        if size <= self.limit { self.words[size / 64] |= 1 << (size % 64) }
+    }
 
-    if !lowest.is_empty() {
-        lowest.sort();
-        return *lowest.first().unwrap();
+    fn union(&mut self, other: &SizeSet) {
+        self.words.iter_mut().zip(other.words.iter()).for_each(|(x, y)| *x |= y);
     }
 
-    Some(current_directory)
+    /// Adds every size in `other`, increased by `offset`.
+    fn union_shifted(&mut self, other: &SizeSet, offset: usize) {
+        let (word_offset, bit_offset) = (offset / 64, offset % 64);
+
+        for index in (word_offset..self.words.len()).rev() {
+            let source = index - word_offset;
+            let mut word = other.words[source] << bit_offset;
+
+            if bit_offset > 0 && source > 0 {
+                word |= other.words[source - 1] >> (64 - bit_offset);
+            }
+
+            self.words[index] |= word;
+        }
+
+        let overflow = self.limit % 64 + 1;
+        if overflow < 64 { *self.words.last_mut().unwrap() &= (1 << overflow) - 1 }
+    }
 }
 
-fn part1(fs: &FileSystem) -> usize {
-    let result = find_clearable_space(&fs.root.as_ref().borrow());
-    result
+/// Finds the non-nested directories that together free the least space that
+/// is still at least `required`, never freeing more than `upper_bound`.
+/// Fails rather than allocate more than `MAX_SIZE_SET_MEMORY`, as it keeps
+/// one set of up to `upper_bound` sizes for every directory.
+///
+/// Walking the directories in pre-order, every directory is either deleted
+/// (jumping past its subtree) or kept (stepping into it), so the reachable
+/// freed sizes at each position form a simple chain of size sets.
+fn find_update_set(directory: &Directory, required: usize, upper_bound: usize) -> Result<Vec<DirectoryEntry>, CleanupError> {
+    let mut entries = vec![];
+    flatten_directories(directory, "/", &mut entries);
+
+    let memory = (upper_bound / 64 + 1).checked_mul(entries.len() + 1).and_then(|words| words.checked_mul(8));
+    if memory.is_none_or(|memory| memory > MAX_SIZE_SET_MEMORY) {
+        return Err(CleanupError::SearchTooLarge { directories: entries.len(), upper_bound });
+    }
+
+    let mut reachable = vec![SizeSet::new(upper_bound); entries.len() + 1];
+    reachable[0].insert(0);
+
+    for (index, (entry, end)) in entries.iter().enumerate() {
+        let current = reachable[index].clone();
+
+        reachable[index + 1].union(&current);
+        reachable[*end].union_shifted(&current, entry.size);
+    }
+
+    let mut size = (required..=upper_bound).find(|&size| reachable[entries.len()].contains(size))
+        .ok_or(CleanupError::NotEnoughSpace)?;
+    let mut position = entries.len();
+    let mut selection = vec![];
+
+    while position > 0 {
+        let deleted = entries.iter().enumerate().find(|(index, (entry, end))| {
+            *end == position && entry.size <= size && reachable[*index].contains(size - entry.size)
+        });
+
+        if let Some((index, (entry, _))) = deleted {
+            size -= entry.size;
+            position = index;
+            selection.push(entry.clone());
+        } else {
+            position -= 1;
+        }
+    }
+
+    selection.reverse();
+
+    Ok(selection)
+}
+
+fn plan_cleanup(fs: &FileSystem, disk_capacity: usize, target_free: usize, strategy: CleanupStrategy) -> Result<CleanupPlan, CleanupError> {
+    let root = fs.root.as_ref().borrow();
+
+    let used_space      = root.total_size();
+    let unused_space    = disk_capacity.saturating_sub(used_space);
+    let required        = target_free.saturating_sub(unused_space);
+
+    if required == 0 { return Ok(CleanupPlan { required, directories: vec![] }) }
+
+    let single = find_update_space(&root, "/", required).ok_or(CleanupError::NotEnoughSpace)?;
+
+    let directories = match strategy {
+        CleanupStrategy::SingleDirectory => vec![single],
+        CleanupStrategy::MinimalSet => find_update_set(&root, required, single.size)?,
+    };
+
+    Ok(CleanupPlan { required, directories })
+}
+
+fn part1(fs: &FileSystem, threshold: usize) -> usize {
+    find_clearable_space(&fs.root.as_ref().borrow(), threshold)
+}
+
+fn part2(fs: &FileSystem, disk_capacity: usize, target_free: usize) -> usize {
+    let plan = plan_cleanup(fs, disk_capacity, target_free, CleanupStrategy::SingleDirectory);
+    plan.expect("No directory frees enough space").freed()
 }
 
-fn part2(fs: &FileSystem) -> usize {
-    let used_space      = fs.root.as_ref().borrow().total_size();
-    let unused_space    = AVAILABLE_DISK_SPACE - used_space;
-    let space_to_find   = REQUIRED_UNUSED_SPACE - unused_space;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Directory,
+    Json,
+    Tar,
+}
 
-    let closest = find_update_space(&fs.root.as_ref().borrow(), &space_to_find);
-    closest.unwrap()
+impl ExportFormat {
+    fn parse(s: &str) -> Option<ExportFormat> {
+        Some(match s {
+            "dir"   => ExportFormat::Directory,
+            "json"  => ExportFormat::Json,
+            "tar"   => ExportFormat::Tar,
+            _       => return None,
+        })
+    }
+}
+
+fn run_export(fs: &FileSystem, format: Option<&str>, destination: Option<PathBuf>) -> io::Result<()> {
+    let format = format.and_then(ExportFormat::parse)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "usage: day7 export <dir|json|tar> [path]"))?;
+
+    let root = fs.root.as_ref().borrow();
+
+    match format {
+        ExportFormat::Directory => {
+            let destination = destination.unwrap_or_else(|| env::temp_dir().join("day7"));
+            export::export_to_directory(&root, &destination)?;
+            println!("exported to {}", destination.display());
+            Ok(())
+        },
+        ExportFormat::Json | ExportFormat::Tar => {
+            // Only create the file once the format is known to be valid, so a typo does not truncate it.
+            let mut writer: Box<dyn Write> = match &destination {
+                Some(path)  => Box::new(BufWriter::new(std::fs::File::create(path)?)),
+                None        => Box::new(BufWriter::new(io::stdout().lock())),
+            };
+
+            if format == ExportFormat::Json {
+                export::export_json(&root, &mut writer)?;
+            } else {
+                export::export_tar(&root, &mut writer)?;
+            }
+
+            writer.flush()
+        },
+    }
 }
 
 fn main() {
     let input = include_str!("input.txt");
-    let fs = FileSystem::from_str(input).unwrap();
+    let (fs, warnings) = match FileSystem::parse(input) {
+        Ok(result) => result,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+    };
 
-    println!("part1: {}", part1(&fs));
-    println!("part2: {}", part2(&fs));
+    warnings.iter().for_each(|warning| eprintln!("warning: {}", warning));
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    if let Some("export") = args.first().map(String::as_str) {
+        if let Err(error) = run_export(&fs, args.get(1).map(String::as_str), args.get(2).map(PathBuf::from)) {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    println!("part1: {}", part1(&fs, CLEARABLE_THRESHOLD));
+    println!("part2: {}", part2(&fs, AVAILABLE_DISK_SPACE, REQUIRED_UNUSED_SPACE));
+
+    match plan_cleanup(&fs, AVAILABLE_DISK_SPACE, REQUIRED_UNUSED_SPACE, CleanupStrategy::MinimalSet) {
+        Ok(plan) => {
+            println!("minimal set: {} of {} required", plan.freed(), plan.required);
+            plan.directories.iter().for_each(|x| println!("  {} {}", x.size, x.path));
+        },
+        Err(error) => eprintln!("minimal set: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn size_at(fs: &FileSystem, path: &[&str]) -> usize {
+        let mut node = fs.root.clone();
+
+        for name in path {
+            let next = node.borrow().subdirectory(name).unwrap().clone();
+            node = next;
+        }
+
+        let size = node.borrow().total_size();
+        size
+    }
+
+    #[test]
+    fn repeated_listings_are_not_counted_twice() {
+        let (fs, warnings) = FileSystem::parse("$ cd /\n$ ls\n100 a.txt\ndir b\n$ ls\n100 a.txt\ndir b\n").unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(size_at(&fs, &[]), 100);
+        assert_eq!(fs.root.borrow().subdirectories.len(), 1);
+    }
+
+    #[test]
+    fn absolute_and_nested_paths_can_be_entered() {
+        let input = "$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\ndir b\n$ cd /a/b\n$ ls\n10 c\n$ cd /\n$ cd a/b\n$ ls\n20 d\n";
+        let (fs, warnings) = FileSystem::parse(input).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(size_at(&fs, &["a", "b"]), 30);
+        assert_eq!(size_at(&fs, &[]), 30);
+    }
+
+    #[test]
+    fn moving_above_the_root_is_an_error() {
+        let error = FileSystem::parse("$ cd /\n$ ls\n10 a\n$ cd ..\n").unwrap_err();
+
+        assert_eq!(error, ParseError { line: 4, kind: ParseErrorKind::AboveRoot });
+    }
+
+    #[test]
+    fn unknown_commands_are_skipped_with_a_warning() {
+        let (fs, warnings) = FileSystem::parse("$ cd /\n$ du -h\n999 a\ndir b\n$ ls\n10 c\n").unwrap();
+
+        assert_eq!(warnings, vec![ParseWarning { line: 2, kind: ParseWarningKind::UnknownCommand(String::from("du -h")) }]);
+        assert_eq!(size_at(&fs, &[]), 10);
+        assert!(fs.root.borrow().subdirectories.is_empty());
+    }
+
+    #[test]
+    fn output_without_ls_is_an_error_on_its_line() {
+        let error = FileSystem::parse("$ cd /\n$ ls\n10 a\n$ cd /\n20 b\n").unwrap_err();
+
+        assert_eq!(error, ParseError { line: 5, kind: ParseErrorKind::OutputWithoutListing(String::from("20 b")) });
+    }
+
+    #[test]
+    fn files_cannot_be_entered() {
+        let error = FileSystem::parse("$ cd /\n$ ls\n10 a\n$ cd a\n").unwrap_err();
+
+        assert_eq!(error, ParseError { line: 4, kind: ParseErrorKind::NotADirectory(String::from("a")) });
+    }
+
+    #[test]
+    fn minimal_set_refuses_searches_that_need_too_much_memory() {
+        let (fs, _) = FileSystem::parse("$ cd /\n$ ls\ndir a\n$ cd a\n$ ls\n1000000000000 big\n").unwrap();
+        let plan = plan_cleanup(&fs, 1_000_000_000_000, 500_000_000_000, CleanupStrategy::MinimalSet);
+
+        assert!(matches!(plan, Err(CleanupError::SearchTooLarge { .. })));
+        assert!(plan_cleanup(&fs, 1_000_000_000_000, 500_000_000_000, CleanupStrategy::SingleDirectory).is_ok());
+    }
+
+    const EXAMPLE: &str = "\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+";
+
+    /// The least space any set of non-nested directories frees while freeing at least `required`, by trying them all.
+    fn brute_force_minimum(fs: &FileSystem, required: usize) -> Option<usize> {
+        let mut entries = vec![];
+        flatten_directories(&fs.root.borrow(), "/", &mut entries);
+
+        (0..1usize << entries.len())
+            .filter(|set| entries.iter().enumerate().all(|(index, (_, end))| {
+                set & (1 << index) == 0 || (index + 1..*end).all(|nested| set & (1 << nested) == 0)
+            }))
+            .map(|set| entries.iter().enumerate().filter(|(index, _)| set & (1 << index) != 0).map(|(_, (entry, _))| entry.size).sum())
+            .filter(|&freed| freed >= required)
+            .min()
+    }
+
+    #[test]
+    fn minimal_set_on_the_example() {
+        let (fs, _) = FileSystem::parse(EXAMPLE).unwrap();
+        let plan = plan_cleanup(&fs, 70_000_000, 30_000_000, CleanupStrategy::MinimalSet).unwrap();
+
+        assert_eq!(plan.required, 8_381_165);
+        assert!(plan.freed() >= plan.required);
+        assert_eq!(Some(plan.freed()), brute_force_minimum(&fs, plan.required));
+        assert_eq!(plan.directories, vec![DirectoryEntry { path: String::from("/d"), size: 24_933_642 }]);
+    }
+
+    #[test]
+    fn minimal_set_beats_a_single_directory() {
+        let input = "$ cd /\n$ ls\ndir b\ndir c\ndir d\n$ cd b\n$ ls\n30 x\n$ cd /c\n$ ls\n25 y\n$ cd /d\n$ ls\n100 z\n";
+        let (fs, _) = FileSystem::parse(input).unwrap();
+
+        let single = plan_cleanup(&fs, 200, 95, CleanupStrategy::SingleDirectory).unwrap();
+        let set = plan_cleanup(&fs, 200, 95, CleanupStrategy::MinimalSet).unwrap();
+
+        assert_eq!((single.required, single.freed()), (50, 100));
+        assert_eq!(set.freed(), 55);
+        assert_eq!(Some(set.freed()), brute_force_minimum(&fs, set.required));
+        assert_eq!(set.directories.iter().map(|x| x.path.as_str()).collect::<Vec<_>>(), ["/b", "/c"]);
+    }
 }
@@ -1,4 +1,10 @@
-use std::{str::FromStr, cmp::max};
+mod render;
+mod sight;
+
+use std::{str::FromStr, cmp::max, env, fs, io::{self, Write}};
+
+use render::Colouring;
+use sight::{Scoring, EIGHT_DIRECTIONS};
 
 struct Grid {
     width: usize,
@@ -8,7 +14,7 @@ struct Grid {
 
 impl Grid {
     pub fn to_coordinate(&self, index: usize) -> (usize, usize) {
-        (index % self.width, index / self.height)
+        (index % self.width, index / self.width)
     }
 
     pub fn entry(&self, x: i32, y: i32) -> Option<&u32> {
@@ -30,7 +36,7 @@ impl FromStr for Grid {
         let height  = lines.len();
 
         let trees = lines.iter().fold(Vec::new(), |mut acc: Vec<u32>, line| {
-            line.chars().into_iter().map(|x| x as u32 - '0' as u32).for_each(|x| acc.push(x));
+            line.chars().map(|x| x as u32 - '0' as u32).for_each(|x| acc.push(x));
             acc
         });
 
@@ -39,7 +45,7 @@ impl FromStr for Grid {
 }
 const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
 
-fn solve(grid: &Grid) -> (usize, usize) {
+fn solve_naive(grid: &Grid) -> (usize, usize) {
     grid.trees.iter().enumerate().map(|(index, tree_height)| {
         let mut scenic_score = 1;
         let mut any_visible = false;
@@ -63,7 +69,7 @@ fn solve(grid: &Grid) -> (usize, usize) {
                 (tx, ty) = ((tx + dx), (ty + dy))
             }
 
-            if visible == true {
+            if visible {
                 any_visible = true;
             }
 
@@ -80,12 +86,250 @@ fn solve(grid: &Grid) -> (usize, usize) {
     })
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TreeView {
+    /// Indexed like `DIRECTIONS`: whether the tree can be seen from the edge in that direction.
+    visible_from: [bool; 4],
+    /// Indexed like `DIRECTIONS`: how many trees can be seen looking in that direction.
+    view_distance: [usize; 4],
+}
+
+impl TreeView {
+    fn is_visible(&self) -> bool {
+        self.visible_from.iter().any(|&x| x)
+    }
+
+    fn scenic_score(&self) -> usize {
+        self.view_distance.iter().product()
+    }
+}
+
+struct ForestView {
+    width: usize,
+    height: usize,
+    trees: Vec<TreeView>,
+}
+
+impl ForestView {
+    fn entry(&self, x: usize, y: usize) -> Option<&TreeView> {
+        if x >= self.width || y >= self.height { return None; }
+
+        self.trees.get(x + y * self.width)
+    }
+
+    fn visible_count(&self) -> usize {
+        self.trees.iter().filter(|x| x.is_visible()).count()
+    }
+
+    fn max_scenic_score(&self) -> usize {
+        self.trees.iter().map(TreeView::scenic_score).max().unwrap_or(0)
+    }
+
+    /// The coordinate of the tree with the highest scenic score, preferring the first one in reading order.
+    fn best_tree(&self) -> Option<(usize, usize)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .rev()
+            .max_by_key(|&(x, y)| self.entry(x, y).unwrap().scenic_score())
+    }
+}
+
+/// Walks one row or column starting at the edge the trees look towards, keeping
+/// a stack of trees that are not yet hidden behind a taller or equal tree.
+/// After popping everything lower than the current tree, the top of the stack is
+/// the tree that blocks its view, or the stack is empty and the edge is visible.
+fn sweep(grid: &Grid, line: &[usize], direction: usize, views: &mut [TreeView]) {
+    let mut stack: Vec<usize> = Vec::with_capacity(line.len());
+
+    for (position, &index) in line.iter().enumerate() {
+        let height = grid.trees[index];
+
+        while let Some(&top) = stack.last() {
+            if grid.trees[line[top]] >= height { break }
+            stack.pop();
+        }
+
+        let view = &mut views[index];
+
+        match stack.last() {
+            Some(&blocker) => view.view_distance[direction] = position - blocker,
+            None => {
+                view.visible_from[direction] = true;
+                view.view_distance[direction] = position;
+            }
+        }
+
+        stack.push(position);
+    }
+}
+
+fn solve(grid: &Grid) -> ForestView {
+    let mut views = vec![TreeView::default(); grid.trees.len()];
+
+    for y in 0..grid.height {
+        let mut row = (0..grid.width).map(|x| x + y * grid.width).collect::<Vec<_>>();
+
+        sweep(grid, &row, 2, &mut views);
+        row.reverse();
+        sweep(grid, &row, 3, &mut views);
+    }
+
+    for x in 0..grid.width {
+        let mut column = (0..grid.height).map(|y| x + y * grid.width).collect::<Vec<_>>();
+
+        sweep(grid, &column, 0, &mut views);
+        column.reverse();
+        sweep(grid, &column, 1, &mut views);
+    }
+
+    ForestView { width: grid.width, height: grid.height, trees: views }
+}
+
+const IMAGE_SCALE: usize = 8;
+
+fn run_render(grid: &Grid, forest: &ForestView, args: &[String]) -> io::Result<()> {
+    let usage = || io::Error::new(io::ErrorKind::InvalidInput,
+        "usage: day8 render <height|visibility|up|down|left|right|scenic> [ansi|ppm|png] [path]");
+
+    let colouring = args.first().and_then(|x| Colouring::parse(x)).ok_or_else(usage)?;
+
+    let output = match args.get(1).map(String::as_str).unwrap_or("ansi") {
+        "ansi"  => render::render_ansi(grid, forest, colouring).into_bytes(),
+        "ppm"   => render::render_ppm(grid, forest, colouring, IMAGE_SCALE),
+        "png"   => render::render_png(grid, forest, colouring, IMAGE_SCALE),
+        _       => return Err(usage()),
+    };
+
+    match args.get(2) {
+        Some(path)  => fs::write(path, output),
+        None        => io::stdout().lock().write_all(&output),
+    }
+}
+
+fn parse_directions(args: &[String]) -> Option<Vec<(i32, i32)>> {
+    match args.first().map(String::as_str) {
+        None | Some("cardinal") => Some(DIRECTIONS.to_vec()),
+        Some("eight")           => Some(EIGHT_DIRECTIONS.to_vec()),
+        _ => args.iter().map(|arg| {
+            let (dx, dy) = arg.split_once(',')?;
+            sight::normalise_direction((dx.parse().ok()?, dy.parse().ok()?))
+        }).collect(),
+    }
+}
+
+fn run_vantage(grid: &Grid, args: &[String]) -> io::Result<()> {
+    let usage = || io::Error::new(io::ErrorKind::InvalidInput,
+        "usage: day8 vantage <sum|min|product> [tree|<eye height>] [cardinal|eight|<dx,dy>...]");
+
+    let scoring = args.first().and_then(|x| Scoring::parse(x)).ok_or_else(usage)?;
+
+    let eye_height = match args.get(1).map(String::as_str) {
+        None | Some("tree") => None,
+        Some(height)        => Some(height.parse().map_err(|_| usage())?),
+    };
+
+    let directions = parse_directions(args.get(2..).unwrap_or(&[])).ok_or_else(usage)?;
+
+    match grid.best_vantage_point(&directions, eye_height, |distances| scoring.score(distances)) {
+        Some(((x, y), score)) => println!("best vantage point: ({}, {}) scoring {}", x, y, score),
+        None => println!("the forest is empty"),
+    }
+
+    Ok(())
+}
+
+fn run_visible(grid: &Grid, args: &[String]) -> io::Result<()> {
+    let directions = parse_directions(args).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput,
+        "usage: day8 visible [cardinal|eight|<dx,dy>...]"))?;
+
+    let visible = (0..grid.trees.len())
+        .map(|index| grid.to_coordinate(index))
+        .filter(|&(x, y)| grid.is_visible_from_any(x, y, &directions))
+        .count();
+
+    println!("visible trees: {}", visible);
+
+    Ok(())
+}
+
 fn main() {
     let input = include_str!("input.txt");
     let grid = Grid::from_str(input).unwrap();
 
-    let (part1, part2) = solve(&grid);
+    let forest = solve(&grid);
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    let command = match args.first().map(String::as_str) {
+        Some("render")  => Some(run_render(&grid, &forest, &args[1..])),
+        Some("vantage") => Some(run_vantage(&grid, &args[1..])),
+        Some("visible") => Some(run_visible(&grid, &args[1..])),
+        _               => None,
+    };
+
+    if let Some(result) = command {
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+            std::process::exit(1);
+        }
+
+        return;
+    }
+
+    let (part1, part2) = (forest.visible_count(), forest.max_scenic_score());
+
+    debug_assert_eq!((part1, part2), solve_naive(&grid));
 
     println!("part1: {}", part1);
     println!("part2: {}", part2);
+
+    if let Some((x, y)) = forest.best_tree() {
+        println!("best tree: ({}, {})", x, y);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "30373\n25512\n65332\n33549\n35390\n";
+
+    fn check(grid: &Grid) {
+        let forest = solve(grid);
+
+        assert_eq!((forest.visible_count(), forest.max_scenic_score()), solve_naive(grid));
+    }
+
+    #[test]
+    fn example_matches_naive_solver() {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+        let forest = solve(&grid);
+
+        assert_eq!((forest.visible_count(), forest.max_scenic_score()), (21, 8));
+        check(&grid);
+    }
+
+    #[test]
+    fn generated_grids_match_naive_solver() {
+        let mut state = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..500 {
+            let width = 1 + (next() % 12) as usize;
+            let height = 1 + (next() % 12) as usize;
+            // Few distinct heights so that equal trees block each other often.
+            let levels = 1 + next() % 10;
+
+            let input = (0..height).map(|_| {
+                (0..width).map(|_| char::from(b'0' + (next() % levels) as u8)).collect::<String>() + "\n"
+            }).collect::<String>();
+
+            check(&Grid::from_str(&input).unwrap());
+        }
+    }
 }
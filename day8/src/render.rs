@@ -0,0 +1,237 @@
+use std::fmt::Write;
+
+use super::{Grid, ForestView, DIRECTIONS};
+
+type Rgb = (u8, u8, u8);
+
+const BEST_TREE: Rgb = (230, 30, 30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colouring {
+    Height,
+    /// Visibility from the edge in one of `DIRECTIONS`, or from any edge if `None`.
+    Visibility(Option<usize>),
+    ScenicScore,
+}
+
+impl Colouring {
+    pub fn parse(s: &str) -> Option<Colouring> {
+        Some(match s {
+            "height"        => Colouring::Height,
+            "visibility"    => Colouring::Visibility(None),
+            "up"            => Colouring::Visibility(Some(0)),
+            "down"          => Colouring::Visibility(Some(1)),
+            "left"          => Colouring::Visibility(Some(2)),
+            "right"         => Colouring::Visibility(Some(3)),
+            "scenic"        => Colouring::ScenicScore,
+            _               => return None,
+        })
+    }
+}
+
+fn lerp(from: Rgb, to: Rgb, t: f64) -> Rgb {
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t.clamp(0.0, 1.0)).round() as u8;
+    (channel(from.0, to.0), channel(from.1, to.1), channel(from.2, to.2))
+}
+
+/// Colours every tree in reading order.
+fn colours(grid: &Grid, forest: &ForestView, colouring: Colouring) -> Vec<Rgb> {
+    let max_score = forest.max_scenic_score().max(1) as f64;
+    let best = forest.best_tree().map(|(x, y)| x + y * grid.width);
+
+    grid.trees.iter().zip(forest.trees.iter()).enumerate().map(|(index, (height, view))| {
+        if Some(index) == best { return BEST_TREE }
+
+        match colouring {
+            Colouring::Height => lerp((20, 50, 20), (140, 230, 110), *height as f64 / 9.0),
+            Colouring::Visibility(Some(direction)) => {
+                if view.visible_from[direction] { (240, 200, 60) } else { (40, 40, 50) }
+            },
+            Colouring::Visibility(None) => {
+                let edges = view.visible_from.iter().filter(|&&x| x).count();
+                lerp((40, 40, 50), (240, 200, 60), edges as f64 / DIRECTIONS.len() as f64)
+            },
+            Colouring::ScenicScore => {
+                let t = (view.scenic_score() as f64 + 1.0).ln() / (max_score + 1.0).ln();
+                if t < 0.5 { lerp((30, 20, 80), (30, 150, 140), t * 2.0) } else { lerp((30, 150, 140), (250, 230, 40), t * 2.0 - 1.0) }
+            },
+        }
+    }).collect()
+}
+
+/// Renders the forest with 24-bit ANSI background colours, two columns per tree.
+pub fn render_ansi(grid: &Grid, forest: &ForestView, colouring: Colouring) -> String {
+    let colours = colours(grid, forest, colouring);
+    let mut output = String::new();
+
+    for (y, row) in colours.chunks(grid.width).enumerate() {
+        for (x, (r, g, b)) in row.iter().enumerate() {
+            let height = grid.trees[x + y * grid.width];
+            let foreground = if (*r as u32 + *g as u32 + *b as u32) > 384 { 0 } else { 255 };
+
+            write!(output, "\x1b[48;2;{};{};{}m\x1b[38;2;{f};{f};{f}m{} ", r, g, b, height, f = foreground).unwrap();
+        }
+
+        output.push_str("\x1b[0m\n");
+    }
+
+    output
+}
+
+/// Expands every tree into a `scale` by `scale` square, returning the image size and its RGB rows.
+fn pixels(grid: &Grid, forest: &ForestView, colouring: Colouring, scale: usize) -> (usize, usize, Vec<u8>) {
+    let colours = colours(grid, forest, colouring);
+    let (width, height) = (grid.width * scale, grid.height * scale);
+
+    let mut data = Vec::with_capacity(width * height * 3);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = colours[x / scale + (y / scale) * grid.width];
+            data.extend_from_slice(&[r, g, b]);
+        }
+    }
+
+    (width, height, data)
+}
+
+pub fn render_ppm(grid: &Grid, forest: &ForestView, colouring: Colouring, scale: usize) -> Vec<u8> {
+    let (width, height, data) = pixels(grid, forest, colouring, scale);
+
+    let mut output = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    output.extend(data);
+    output
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+
+    (b << 16) | a
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut output = vec![0x78, 0x01];
+    let blocks = data.chunks(u16::MAX as usize).collect::<Vec<_>>();
+
+    if blocks.is_empty() { output.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]) }
+
+    for (index, block) in blocks.iter().enumerate() {
+        let length = block.len() as u16;
+
+        output.push((index + 1 == blocks.len()) as u8);
+        output.extend_from_slice(&length.to_le_bytes());
+        output.extend_from_slice(&(!length).to_le_bytes());
+        output.extend_from_slice(block);
+    }
+
+    output.extend_from_slice(&adler32(data).to_be_bytes());
+    output
+}
+
+fn png_chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = output.len();
+    output.extend_from_slice(kind);
+    output.extend_from_slice(data);
+
+    let crc = crc32(&output[start..]);
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+pub fn render_png(grid: &Grid, forest: &ForestView, colouring: Colouring, scale: usize) -> Vec<u8> {
+    let (width, height, data) = pixels(grid, forest, colouring, scale);
+
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut scanlines = Vec::with_capacity(data.len() + height);
+    for row in data.chunks(width * 3) {
+        scanlines.push(0);
+        scanlines.extend_from_slice(row);
+    }
+
+    let mut output = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+    png_chunk(&mut output, b"IHDR", &header);
+    png_chunk(&mut output, b"IDAT", &zlib_stored(&scanlines));
+    png_chunk(&mut output, b"IEND", &[]);
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::solve;
+
+    const EXAMPLE: &str = "30373\n25512\n65332\n33549\n35390\n";
+
+    fn example() -> (Grid, ForestView) {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+        let forest = solve(&grid);
+        (grid, forest)
+    }
+
+    #[test]
+    fn ppm_has_header_and_one_pixel_per_scaled_tree() {
+        let (grid, forest) = example();
+        let image = render_ppm(&grid, &forest, Colouring::Height, 3);
+
+        let header = b"P6\n15 15\n255\n";
+        assert_eq!(&image[..header.len()], header);
+        assert_eq!(image.len(), header.len() + 15 * 15 * 3);
+    }
+
+    #[test]
+    fn crc32_matches_the_reference_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn png_has_signature_header_and_valid_chunks() {
+        let (grid, forest) = example();
+        let image = render_png(&grid, &forest, Colouring::ScenicScore, 2);
+
+        assert_eq!(&image[..8], b"\x89PNG\r\n\x1a\n");
+
+        let mut chunks = vec![];
+        let mut rest = &image[8..];
+
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (body, crc) = (&rest[4..8 + length], &rest[8 + length..12 + length]);
+
+            assert_eq!(crc32(body).to_be_bytes(), crc);
+
+            chunks.push((body[..4].to_vec(), body[4..].to_vec()));
+            rest = &rest[12 + length..];
+        }
+
+        let kinds = chunks.iter().map(|(kind, _)| kind.as_slice()).collect::<Vec<_>>();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+
+        // 10x10, 8-bit truecolour, default compression, filtering and no interlace.
+        assert_eq!(chunks[0].1, [0, 0, 0, 10, 0, 0, 0, 10, 8, 2, 0, 0, 0]);
+    }
+}
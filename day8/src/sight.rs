@@ -0,0 +1,199 @@
+use super::Grid;
+
+pub const EIGHT_DIRECTIONS: [(i32, i32); 8] = [
+    (0, -1), (0, 1), (-1, 0), (1, 0),
+    (-1, -1), (1, -1), (-1, 1), (1, 1),
+];
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// Reduces a slope to the smallest integer step that stays on the same line,
+/// so `(2, 4)` and `(1, 2)` look along the same trees.
+pub fn normalise_direction((dx, dy): (i32, i32)) -> Option<(i32, i32)> {
+    let divisor = gcd(dx, dy);
+    if divisor == 0 { return None }
+
+    Some((dx / divisor, dy / divisor))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scoring {
+    Sum,
+    Min,
+    Product,
+}
+
+impl Scoring {
+    pub fn parse(s: &str) -> Option<Scoring> {
+        Some(match s {
+            "sum"       => Scoring::Sum,
+            "min"       => Scoring::Min,
+            "product"   => Scoring::Product,
+            _           => return None,
+        })
+    }
+
+    pub fn score(&self, view_distances: &[usize]) -> usize {
+        match self {
+            Scoring::Sum        => view_distances.iter().sum(),
+            Scoring::Min        => view_distances.iter().copied().min().unwrap_or(0),
+            Scoring::Product    => view_distances.iter().product(),
+        }
+    }
+}
+
+impl Grid {
+    /// The trees seen walking from `(x, y)` along `direction`, excluding the starting tree.
+    pub fn ray(&self, x: usize, y: usize, direction: (i32, i32)) -> impl Iterator<Item = u32> + '_ {
+        let (dx, dy) = normalise_direction(direction).unwrap_or((0, 0));
+        let (mut tx, mut ty) = (x as i32, y as i32);
+
+        std::iter::from_fn(move || {
+            if (dx, dy) == (0, 0) { return None }
+
+            (tx, ty) = (tx + dx, ty + dy);
+            self.entry(tx, ty).copied()
+        })
+    }
+
+    /// How many trees an observer with their eyes at `eye_height`, standing at `(x, y)`,
+    /// can see along `direction` before a tree at least as tall blocks the view.
+    pub fn view_distance(&self, x: usize, y: usize, direction: (i32, i32), eye_height: u32) -> usize {
+        let mut distance = 0;
+
+        for height in self.ray(x, y, direction) {
+            distance += 1;
+            if height >= eye_height { break }
+        }
+
+        distance
+    }
+
+    /// Whether the tree at `(x, y)` can be seen from outside the forest looking back against `direction`.
+    pub fn is_visible_along(&self, x: usize, y: usize, direction: (i32, i32)) -> bool {
+        let Some(&height) = self.entry(x as i32, y as i32) else { return false };
+
+        self.ray(x, y, direction).all(|x| x < height)
+    }
+
+    pub fn is_visible_from_any(&self, x: usize, y: usize, directions: &[(i32, i32)]) -> bool {
+        directions.iter().any(|&direction| self.is_visible_along(x, y, direction))
+    }
+
+    /// Finds the spot with the highest score over its view distances along `directions`.
+    /// The observer stands at `eye_height`, or at the height of the tree they are on if `None`.
+    pub fn best_vantage_point(
+        &self,
+        directions: &[(i32, i32)],
+        eye_height: Option<u32>,
+        score: impl Fn(&[usize]) -> usize,
+    ) -> Option<((usize, usize), usize)> {
+        let mut distances = Vec::with_capacity(directions.len());
+
+        (0..self.trees.len()).rev().map(|index| {
+            let (x, y) = self.to_coordinate(index);
+            let eye_height = eye_height.unwrap_or(self.trees[index]);
+
+            distances.clear();
+            distances.extend(directions.iter().map(|&direction| self.view_distance(x, y, direction, eye_height)));
+
+            ((x, y), score(&distances))
+        }).max_by_key(|&(_, score)| score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::{solve, DIRECTIONS};
+
+    const EXAMPLE: &str = "30373\n25512\n65332\n33549\n35390\n";
+
+    fn scenic_score(grid: &Grid, x: usize, y: usize) -> usize {
+        let height = grid.trees[x + y * grid.width];
+        DIRECTIONS.iter().map(|&direction| grid.view_distance(x, y, direction, height)).product()
+    }
+
+    #[test]
+    fn example_view_distances() {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(DIRECTIONS.map(|direction| grid.view_distance(2, 1, direction, 5)), [1, 2, 1, 2]);
+        assert_eq!(DIRECTIONS.map(|direction| grid.view_distance(2, 3, direction, 5)), [2, 1, 2, 2]);
+        assert_eq!(scenic_score(&grid, 2, 1), 4);
+        assert_eq!(scenic_score(&grid, 2, 3), 8);
+    }
+
+    #[test]
+    fn example_visibility() {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(DIRECTIONS.map(|direction| grid.is_visible_along(1, 1, direction)), [true, false, true, false]);
+        assert_eq!(DIRECTIONS.map(|direction| grid.is_visible_along(3, 3, direction)), [false, false, false, false]);
+        assert!(grid.is_visible_from_any(2, 1, &DIRECTIONS));
+        assert!(!grid.is_visible_from_any(3, 3, &DIRECTIONS));
+    }
+
+    #[test]
+    fn eye_height_is_independent_of_the_tree() {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(grid.view_distance(0, 2, (1, 0), 6), 4);
+        assert_eq!(grid.view_distance(0, 2, (1, 0), 5), 1);
+        assert_eq!(grid.view_distance(2, 1, (0, 1), 4), 2);
+        assert_eq!(grid.view_distance(2, 1, (0, 1), 9), 3);
+        assert_eq!(grid.view_distance(0, 0, (1, 0), 0), 1);
+    }
+
+    #[test]
+    fn diagonals_and_slopes() {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+
+        assert_eq!(grid.ray(0, 0, (1, 1)).collect::<Vec<_>>(), [5, 3, 4, 0]);
+        assert_eq!(grid.ray(0, 0, (2, 2)).collect::<Vec<_>>(), [5, 3, 4, 0]);
+        assert_eq!(grid.ray(0, 0, (1, 2)).collect::<Vec<_>>(), [5, 3]);
+        assert_eq!(grid.ray(0, 0, (0, 0)).count(), 0);
+
+        assert_eq!(grid.view_distance(0, 0, (1, 1), 3), 1);
+        assert_eq!(grid.view_distance(0, 0, (1, 1), 9), 4);
+        assert_eq!(grid.view_distance(4, 4, (-1, -1), 0), 1);
+
+        assert!(grid.is_visible_along(1, 1, (-1, -1)));
+        assert!(!grid.is_visible_along(3, 1, (1, -1)));
+        assert!(!grid.is_visible_from_any(2, 2, &EIGHT_DIRECTIONS));
+        assert!(grid.is_visible_from_any(3, 0, &EIGHT_DIRECTIONS));
+    }
+
+    #[test]
+    fn best_vantage_point_agrees_with_part2() {
+        let grid = Grid::from_str(EXAMPLE).unwrap();
+        assert_eq!(grid.best_vantage_point(&DIRECTIONS, None, |x| Scoring::Product.score(x)), Some(((2, 3), 8)));
+
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..200 {
+            let width = 1 + (next() % 10) as usize;
+            let height = 1 + (next() % 10) as usize;
+
+            let input = (0..height).map(|_| {
+                (0..width).map(|_| char::from(b'0' + (next() % 10) as u8)).collect::<String>() + "\n"
+            }).collect::<String>();
+
+            let grid = Grid::from_str(&input).unwrap();
+            let forest = solve(&grid);
+            let best = grid.best_vantage_point(&DIRECTIONS, None, |x| Scoring::Product.score(x));
+
+            assert_eq!(best, forest.best_tree().map(|tree| (tree, forest.max_scenic_score())));
+        }
+    }
+}
@@ -1,127 +1,370 @@
-use std::{str::FromStr, collections::HashSet};
+mod render;
+mod visited;
+
+use std::{str::FromStr, env, fs, io::{self, BufWriter, Write}, time::Instant};
+
+use render::Bounds;
+use visited::VisitedSet;
 
 #[derive(Debug, Clone, Copy)]
 enum Motion {
-    Up(i32),
-    Down(i32),
-    Left(i32),
-    Right(i32)
+    Up(u32),
+    Down(u32),
+    Left(u32),
+    Right(u32),
+    UpLeft(u32),
+    UpRight(u32),
+    DownLeft(u32),
+    DownRight(u32),
+    Forward(u32),
+    Back(u32),
 }
 
 impl FromStr for Motion {
-    type Err = ();
+    type Err = io::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (identifier, value) = s.split_once(" ").unwrap();
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid motion {:?}", s));
+
+        let (identifier, value) = s.split_once(" ").ok_or_else(invalid)?;
+
+        let value = value.parse::<u32>().map_err(|_| invalid())?;
 
-        let value = value.parse::<i32>().unwrap();
-        
         Ok(match identifier {
-            "U" => Motion::Up(value),
-            "D" => Motion::Down(value),
-            "L" => Motion::Left(value),
-            "R" => Motion::Right(value),
-            _ => unreachable!()
+            "U"     => Motion::Up(value),
+            "D"     => Motion::Down(value),
+            "L"     => Motion::Left(value),
+            "R"     => Motion::Right(value),
+            "UL"    => Motion::UpLeft(value),
+            "UR"    => Motion::UpRight(value),
+            "DL"    => Motion::DownLeft(value),
+            "DR"    => Motion::DownRight(value),
+            "F"     => Motion::Forward(value),
+            "B"     => Motion::Back(value),
+            _ => return Err(invalid()),
         })
     }
 }
 
 impl Motion {
-    fn parse(s: &str) -> Vec<Motion> {
+    fn parse(s: &str) -> io::Result<Vec<Motion>> {
         s.split_terminator('\n')
-            .map(|line| Motion::from_str(line).unwrap())
+            .map(Motion::from_str)
             .collect()
     }
 
-    fn consume(&self) -> Option<Motion> {
+    fn distance(&self) -> u32 {
         match self {
-            Motion::Up(x)       => if *x > 1 { Some(Motion::Up(*x - 1)) } else { None },
-            Motion::Down(x)     => if *x > 1 { Some(Motion::Down(*x - 1)) } else { None },
-            Motion::Left(x)     => if *x > 1 { Some(Motion::Left(*x - 1)) } else { None },
-            Motion::Right(x)    => if *x > 1 { Some(Motion::Right(*x - 1)) } else { None },
+            Motion::Up(x) | Motion::Down(x) | Motion::Left(x) | Motion::Right(x) |
+            Motion::UpLeft(x) | Motion::UpRight(x) | Motion::DownLeft(x) | Motion::DownRight(x) |
+            Motion::Forward(x) | Motion::Back(x) => *x,
         }
     }
-}
 
-struct Bridge {
-    parts: Vec<(i32, i32)>,
-    visited_locations: HashSet<(i32, i32)>,
+    /// The change in `(x, y, z)` caused by a single step of this motion.
+    fn delta(&self) -> [i32; 3] {
+        match self {
+            Motion::Up(_)           => [0, 1, 0],
+            Motion::Down(_)         => [0, -1, 0],
+            Motion::Left(_)         => [-1, 0, 0],
+            Motion::Right(_)        => [1, 0, 0],
+            Motion::UpLeft(_)       => [-1, 1, 0],
+            Motion::UpRight(_)      => [1, 1, 0],
+            Motion::DownLeft(_)     => [-1, -1, 0],
+            Motion::DownRight(_)    => [1, -1, 0],
+            Motion::Forward(_)      => [0, 0, 1],
+            Motion::Back(_)         => [0, 0, -1],
+        }
+    }
+
+    /// The number of axes a rope needs to follow this motion.
+    fn dimensions(&self) -> usize {
+        match self {
+            Motion::Forward(_) | Motion::Back(_) => 3,
+            _ => 2,
+        }
+    }
 }
 
-impl Bridge {
-    fn new(length: usize) -> Bridge {
-        let mut visited_locations = HashSet::new();
-        visited_locations.insert((0, 0));
+type Position<const D: usize> = [i32; D];
+
+struct Bridge<const D: usize> {
+    parts: Vec<Position<D>>,
+    /// Every location each knot has been in, indexed like `parts`.
+    visited_locations: Vec<VisitedSet<Position<D>>>,
+}
 
+impl<const D: usize> Bridge<D> {
+    fn new(length: usize) -> Bridge<D> {
         Bridge {
-            parts: (0..length).into_iter().map(|_| (0, 0)).collect(),
-            visited_locations,
+            parts: vec![[0; D]; length],
+            visited_locations: (0..length).map(|_| {
+                let mut visited = VisitedSet::new();
+                visited.insert([0; D]);
+                visited
+            }).collect(),
         }
     }
 
-    fn apply_motion(&mut self, motion: &Motion) {
-        let mut action = Some(motion.clone());
+    fn knots(&self) -> &[Position<D>] {
+        &self.parts
+    }
 
-        while let Some(direction) = action {
-            let (hx, hy) = self.parts.first_mut().unwrap();
+    /// Moves the head a single step by `delta`, dragging the rest of the rope along.
+    /// Once a knot stays put, every knot behind it does too, so the walk stops there.
+    fn step(&mut self, delta: Position<D>) {
+        let head = self.parts.first_mut().unwrap();
+        head.iter_mut().zip(delta).for_each(|(axis, delta)| *axis += delta);
 
-            match direction {
-                Motion::Up(_)       => *hy += 1,
-                Motion::Down(_)     => *hy -= 1,
-                Motion::Left(_)     => *hx -= 1,
-                Motion::Right(_)    => *hx += 1,
-            }
+        self.visited_locations[0].insert(self.parts[0]);
 
-            for index in 1..self.parts.len() {
-                let (hx, hy) = self.parts.get(index - 1).unwrap();
-                let (tx, ty) = self.parts.get(index).unwrap();
+        for index in 1..self.parts.len() {
+            let head = self.parts[index - 1];
+            let tail = self.parts[index];
 
-                let (dx, dy) = ((*hx - *tx), (*hy - *ty));
-                
-                if dx.abs() > 1 || dy.abs() > 1 {
-                    let x = dx.clamp(-1, 1);
-                    let y = dy.clamp(-1, 1);
+            let difference: Position<D> = std::array::from_fn(|axis| head[axis] - tail[axis]);
 
-                    let new_pos = (*tx + x, *ty + y);
+            if difference.iter().all(|x| x.abs() <= 1) { break }
 
-                    if index == self.parts.len() - 1 { self.visited_locations.insert(new_pos); }
+            let new_pos = std::array::from_fn(|axis| tail[axis] + difference[axis].clamp(-1, 1));
 
-                    let old_pos = self.parts.get_mut(index).expect("cannot get mutable of snake part");
-                    old_pos.0 = new_pos.0;
-                    old_pos.1 = new_pos.1;
-                }
-            }
+            self.visited_locations[index].insert(new_pos);
+            self.parts[index] = new_pos;
+        }
+    }
+
+    fn apply_motion(&mut self, motion: &Motion, on_step: &mut impl FnMut(&Bridge<D>)) -> io::Result<()> {
+        let delta = motion.delta();
+
+        if delta[D..].iter().any(|&x| x != 0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                format!("{:?} needs more than {} dimensions", motion, D)));
+        }
+
+        let delta = std::array::from_fn(|axis| delta[axis]);
 
-            action = direction.consume();
+        for _ in 0..motion.distance() {
+            self.step(delta);
+            on_step(self);
         }
+
+        Ok(())
     }
 
-    fn apply_motions(&mut self, motion: &Vec<Motion>) {
-        motion.iter().for_each(|x| self.apply_motion(x))
+    fn apply_motions(&mut self, motions: &[Motion]) -> io::Result<()> {
+        motions.iter().try_for_each(|x| self.apply_motion(x, &mut |_| {}))
+    }
+
+    /// Applies every motion, returning the position of every knot after every step, starting with the initial state.
+    fn record(&mut self, motions: &[Motion]) -> io::Result<Vec<Vec<Position<D>>>> {
+        let mut frames = vec![self.parts.clone()];
+
+        motions.iter().try_for_each(|x| self.apply_motion(x, &mut |bridge| frames.push(bridge.knots().to_vec())))?;
+
+        Ok(frames)
     }
 }
 
-fn part1(motions: &Vec<Motion>) -> usize {
-    let mut bridge = Bridge::new(2);
+/// The number of locations visited by every knot of a rope of `length` knots, in the fewest dimensions the motions fit in.
+fn count_visited(motions: &[Motion], length: usize) -> io::Result<Vec<usize>> {
+    fn count<const D: usize>(motions: &[Motion], length: usize) -> io::Result<Vec<usize>> {
+        let mut bridge = Bridge::<D>::new(length);
+        bridge.apply_motions(motions)?;
+        Ok(bridge.visited_locations.iter_mut().map(VisitedSet::count).collect())
+    }
 
-    bridge.apply_motions(&motions);
+    match motions.iter().map(Motion::dimensions).max().unwrap_or(2) {
+        2 => count::<2>(motions, length),
+        _ => count::<3>(motions, length),
+    }
+}
 
-    bridge.visited_locations.len()
+fn part1(motions: &[Motion]) -> io::Result<usize> {
+    Ok(*count_visited(motions, 2)?.last().unwrap())
 }
 
-fn part2(motions: &Vec<Motion>) -> usize {
-    let mut bridge = Bridge::new(10);
+fn part2(motions: &[Motion]) -> io::Result<usize> {
+    Ok(*count_visited(motions, 10)?.last().unwrap())
+}
 
-    bridge.apply_motions(&motions);
+/// Writes random cardinal motions totalling at least `steps` steps, using a xorshift generator seeded with `seed`.
+fn generate_motions(writer: &mut impl Write, steps: usize, seed: u64) -> io::Result<()> {
+    let mut state = seed.max(1);
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
 
-    bridge.visited_locations.len()
+    let mut total = 0;
+
+    while total < steps {
+        let value = next();
+        let direction = ["U", "D", "L", "R"][(value % 4) as usize];
+        let distance = (value >> 2) % 20 + 1;
+
+        writeln!(writer, "{} {}", direction, distance)?;
+        total += distance as usize;
+    }
+
+    Ok(())
+}
+
+/// Times `count_visited` for each rope length over the motions in `path`.
+///
+/// On a 1M step file from `generate 1000000`, release build:
+///
+/// | knots | `HashSet` per knot, full walk | `VisitedSet`, early exit |
+/// |-------|-------------------------------|--------------------------|
+/// | 2     | 0.29-0.35 s                   | 0.20 s                   |
+/// | 10    | 1.4-1.6 s                     | 0.70-0.75 s              |
+/// | 100   | 5.3-5.8 s                     | 1.7-2.1 s                |
+fn run_benchmark(path: &str, lengths: &[usize]) -> io::Result<()> {
+    let motions = Motion::parse(&fs::read_to_string(path)?)?;
+    let steps = motions.iter().map(|x| x.distance() as usize).sum::<usize>();
+
+    println!("{} motions, {} steps", motions.len(), steps);
+
+    for &length in lengths {
+        let start = Instant::now();
+        let visited = count_visited(&motions, length)?;
+        let elapsed = start.elapsed();
+
+        println!("{} knots: tail visited {} in {:.1?} ({:.1} Msteps/s)",
+            length, visited.last().unwrap(), elapsed, steps as f64 / elapsed.as_secs_f64() / 1e6);
+    }
+
+    Ok(())
+}
+
+fn run_command(motions: &[Motion], args: &[String]) -> io::Result<()> {
+    let usage = || io::Error::new(io::ErrorKind::InvalidInput,
+        "usage: day9 <visited|frames|animate> <knots> [output.gif] [every] [scale]\n       \
+                day9 generate <steps> <path> [seed]\n       \
+                day9 bench <path> [knots...]");
+
+    let command = args.first().ok_or_else(usage)?;
+
+    match command.as_str() {
+        "generate" => {
+            let steps = args.get(1).and_then(|x| x.parse().ok()).ok_or_else(usage)?;
+            let path = args.get(2).ok_or_else(usage)?;
+            let seed = args.get(3).and_then(|x| x.parse().ok()).unwrap_or(0x2022_1209);
+
+            let mut writer = BufWriter::new(fs::File::create(path)?);
+            generate_motions(&mut writer, steps, seed)?;
+            return writer.flush();
+        },
+        "bench" => {
+            let path = args.get(1).ok_or_else(usage)?;
+            let lengths = match args.get(2..) {
+                Some(lengths) if !lengths.is_empty() => lengths.iter()
+                    .map(|x| x.parse().ok().filter(|&x| x > 0))
+                    .collect::<Option<Vec<usize>>>()
+                    .ok_or_else(usage)?,
+                _ => vec![2, 10],
+            };
+
+            return run_benchmark(path, &lengths);
+        },
+        _ => {},
+    }
+
+    let knots = args.get(1).and_then(|x| x.parse::<usize>().ok()).filter(|&x| x > 0).ok_or_else(usage)?;
+
+    if command == "visited" {
+        count_visited(motions, knots)?.iter().enumerate()
+            .for_each(|(knot, visited)| println!("knot {}: {}", knot, visited));
+
+        return Ok(());
+    }
+
+    if motions.iter().any(|x| x.dimensions() > 2) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "only two dimensional ropes can be drawn"));
+    }
+
+    let mut bridge = Bridge::<2>::new(knots);
+
+    match command.as_str() {
+        "frames" => {
+            let every = args.get(2).and_then(|x| x.parse::<usize>().ok()).unwrap_or(1).max(1);
+            let frames = bridge.record(motions)?;
+            let bounds = Bounds::of(&frames);
+
+            for (step, knots) in frames.iter().enumerate().step_by(every) {
+                println!("== step {} ==\n{}", step, render::render_frame(knots, &bounds));
+            }
+        },
+        "animate" => {
+            let path = args.get(2).ok_or_else(usage)?;
+            let every = args.get(3).and_then(|x| x.parse::<usize>().ok()).unwrap_or(1).max(1);
+            let scale = args.get(4).and_then(|x| x.parse::<usize>().ok()).unwrap_or(2).max(1);
+
+            let frames = bridge.record(motions)?;
+            let bounds = Bounds::of(&frames);
+
+            let mut writer = BufWriter::new(fs::File::create(path)?);
+            render::write_gif(&mut writer, &frames, &bounds, every, scale)?;
+        },
+        _ => return Err(usage()),
+    }
+
+    Ok(())
+}
+
+fn run(input: &str, args: &[String]) -> io::Result<()> {
+    let motions = Motion::parse(input)?;
+
+    if !args.is_empty() {
+        return run_command(&motions, args);
+    }
+
+    println!("part1: {}", part1(&motions)?);
+    println!("part2: {}", part2(&motions)?);
+
+    Ok(())
 }
 
 fn main() {    
     let input = include_str!("input.txt");
 
-    let motions = Motion::parse(input);
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    if let Err(error) = run(input, &args) {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n";
+    const LARGER_EXAMPLE: &str = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n";
+
+    #[test]
+    fn examples() {
+        let (example, larger) = (Motion::parse(EXAMPLE).unwrap(), Motion::parse(LARGER_EXAMPLE).unwrap());
+
+        assert_eq!(part1(&example).unwrap(), 13);
+        assert_eq!(part2(&example).unwrap(), 1);
+        assert_eq!(part2(&larger).unwrap(), 36);
+    }
+
+    #[test]
+    fn malformed_motions_report_the_line() {
+        for line in ["U", "U x", "X 2", "U -3", "R +"] {
+            let error = Motion::from_str(line).unwrap_err();
+            assert!(error.to_string().contains(line), "{}", error);
+        }
+    }
 
-    println!("part1: {}", part1(&motions));
-    println!("part2: {}", part2(&motions));
+    #[test]
+    fn flat_ropes_refuse_three_dimensional_motions() {
+        let mut bridge = Bridge::<2>::new(2);
+
+        assert!(bridge.apply_motions(&[Motion::Up(1), Motion::Forward(1)]).is_err());
+    }
 }
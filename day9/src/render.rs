@@ -0,0 +1,335 @@
+use std::{collections::{HashMap, HashSet}, io::{self, Write}};
+
+type Position = super::Position<2>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bounds {
+    min: Position,
+    max: Position,
+}
+
+impl Bounds {
+    /// The smallest area containing every knot in every frame, and the starting point.
+    pub fn of(frames: &[Vec<Position>]) -> Bounds {
+        frames.iter().flatten().fold(Bounds { min: [0, 0], max: [0, 0] }, |bounds, &[x, y]| Bounds {
+            min: [bounds.min[0].min(x), bounds.min[1].min(y)],
+            max: [bounds.max[0].max(x), bounds.max[1].max(y)],
+        })
+    }
+
+    fn width(&self) -> usize {
+        (self.max[0] - self.min[0] + 1) as usize
+    }
+
+    fn height(&self) -> usize {
+        (self.max[1] - self.min[1] + 1) as usize
+    }
+
+    /// Turns a position into a row-major index, with the highest `y` on the first row.
+    fn index(&self, [x, y]: Position) -> usize {
+        (x - self.min[0]) as usize + (self.max[1] - y) as usize * self.width()
+    }
+}
+
+fn knot_label(index: usize, length: usize) -> char {
+    match index {
+        0                       => 'H',
+        1 if length == 2        => 'T',
+        1..=9                   => char::from_digit(index as u32, 10).unwrap(),
+        10..=35                 => char::from_digit(index as u32, 36).unwrap(),
+        _                       => '#',
+    }
+}
+
+/// Draws the knots like the puzzle statement does: `H` for the head, then numbered knots, with `s` marking the start.
+pub fn render_frame(knots: &[Position], bounds: &Bounds) -> String {
+    let mut cells = vec!['.'; bounds.width() * bounds.height()];
+    cells[bounds.index([0, 0])] = 's';
+
+    for (index, &knot) in knots.iter().enumerate().rev() {
+        cells[bounds.index(knot)] = knot_label(index, knots.len());
+    }
+
+    cells.chunks(bounds.width()).map(|row| row.iter().collect::<String>()).collect::<Vec<_>>().join("\n")
+}
+
+const PALETTE: [[u8; 3]; 8] = [
+    [16, 16, 24],       // background
+    [70, 70, 110],      // visited by the tail
+    [230, 230, 230],    // rope
+    [240, 70, 50],      // head
+    [80, 200, 90],      // start
+    [0, 0, 0],
+    [0, 0, 0],
+    [0, 0, 0],
+];
+
+const BACKGROUND: u8    = 0;
+const VISITED: u8       = 1;
+const ROPE: u8          = 2;
+const HEAD: u8          = 3;
+const START: u8         = 4;
+
+const MIN_CODE_SIZE: u8 = 3;
+const MAX_CODE: u16     = 4096;
+const FRAME_DELAY: u16  = 2;
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 { self.bytes.push(self.buffer as u8) }
+        self.bytes
+    }
+}
+
+/// Compresses palette indices with the variable-width LZW flavour GIF uses.
+fn lzw_encode(pixels: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut writer = BitWriter { bytes: vec![], buffer: 0, bits: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end + 1;
+    let mut code_size = MIN_CODE_SIZE + 1;
+
+    writer.write(clear, code_size);
+
+    let Some((&first, rest)) = pixels.split_first() else {
+        writer.write(end, code_size);
+        return writer.finish();
+    };
+
+    let mut prefix = first as u16;
+
+    for &pixel in rest {
+        if let Some(&code) = table.get(&(prefix, pixel)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+
+        if next_code < MAX_CODE {
+            table.insert((prefix, pixel), next_code);
+            next_code += 1;
+
+            if next_code > (1 << code_size) && code_size < 12 { code_size += 1 }
+        } else {
+            writer.write(clear, code_size);
+            table.clear();
+            next_code = end + 1;
+            code_size = MIN_CODE_SIZE + 1;
+        }
+
+        prefix = pixel as u16;
+    }
+
+    writer.write(prefix, code_size);
+    writer.write(end, code_size);
+
+    writer.finish()
+}
+
+fn write_frame(writer: &mut impl Write, pixels: &[u8], width: u16, height: u16) -> io::Result<()> {
+    writer.write_all(&[0x21, 0xF9, 4, 0])?;
+    writer.write_all(&FRAME_DELAY.to_le_bytes())?;
+    writer.write_all(&[0, 0])?;
+
+    writer.write_all(&[0x2C, 0, 0, 0, 0])?;
+    writer.write_all(&width.to_le_bytes())?;
+    writer.write_all(&height.to_le_bytes())?;
+    writer.write_all(&[0, MIN_CODE_SIZE])?;
+
+    for block in lzw_encode(pixels).chunks(255) {
+        writer.write_all(&[block.len() as u8])?;
+        writer.write_all(block)?;
+    }
+
+    writer.write_all(&[0])
+}
+
+/// Writes every `every`th frame as a looping GIF, with each cell drawn as a `scale` by `scale` square
+/// and the path of the tail so far left behind as a trail.
+pub fn write_gif(writer: &mut impl Write, frames: &[Vec<Position>], bounds: &Bounds, every: usize, scale: usize) -> io::Result<()> {
+    let (width, height) = (bounds.width() * scale, bounds.height() * scale);
+
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "animation is too large for a GIF, try a smaller scale");
+    let (gif_width, gif_height) = (u16::try_from(width).map_err(|_| too_large())?, u16::try_from(height).map_err(|_| too_large())?);
+
+    writer.write_all(b"GIF89a")?;
+    writer.write_all(&gif_width.to_le_bytes())?;
+    writer.write_all(&gif_height.to_le_bytes())?;
+    writer.write_all(&[0xF0 | (MIN_CODE_SIZE - 1), BACKGROUND, 0])?;
+    PALETTE.iter().try_for_each(|colour| writer.write_all(colour))?;
+
+    writer.write_all(&[0x21, 0xFF, 11])?;
+    writer.write_all(b"NETSCAPE2.0")?;
+    writer.write_all(&[3, 1, 0, 0, 0])?;
+
+    let mut visited = HashSet::new();
+    let mut cells = vec![BACKGROUND; bounds.width() * bounds.height()];
+    let mut pixels = vec![BACKGROUND; width * height];
+
+    for (step, knots) in frames.iter().enumerate() {
+        if let Some(&tail) = knots.last() { visited.insert(tail); }
+
+        if step % every != 0 && step + 1 != frames.len() { continue }
+
+        cells.fill(BACKGROUND);
+        visited.iter().for_each(|&position| cells[bounds.index(position)] = VISITED);
+        cells[bounds.index([0, 0])] = START;
+
+        for (index, &knot) in knots.iter().enumerate().rev() {
+            cells[bounds.index(knot)] = if index == 0 { HEAD } else { ROPE };
+        }
+
+        for (y, row) in pixels.chunks_mut(width).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = cells[x / scale + (y / scale) * bounds.width()];
+            }
+        }
+
+        write_frame(writer, &pixels, gif_width, gif_height)?;
+    }
+
+    writer.write_all(&[0x3B])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Bridge, Motion};
+
+    const EXAMPLE: &str = "R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2\n";
+    const LARGER_EXAMPLE: &str = "R 5\nU 8\nL 8\nD 3\nR 17\nD 10\nL 25\nU 20\n";
+
+    /// Decodes a GIF LZW stream, returning the pixels, how many clear codes were read and the widest code seen.
+    fn lzw_decode(bytes: &[u8]) -> (Vec<u8>, usize, u8) {
+        let clear = 1u16 << MIN_CODE_SIZE;
+        let end = clear + 1;
+
+        let mut table: Vec<Vec<u8>> = (0..=end).map(|x| vec![x as u8]).collect();
+        let (mut position, mut code_size, mut widest) = (0, MIN_CODE_SIZE + 1, 0);
+        let (mut output, mut clears, mut previous) = (vec![], 0, None::<Vec<u8>>);
+
+        loop {
+            let code = (0..code_size as usize).fold(0u16, |code, bit| {
+                let bit_index = position + bit;
+                code | (((bytes[bit_index / 8] >> (bit_index % 8)) & 1) as u16) << bit
+            });
+            position += code_size as usize;
+            widest = widest.max(code_size);
+
+            if code == clear {
+                table.truncate(end as usize + 1);
+                code_size = MIN_CODE_SIZE + 1;
+                clears += 1;
+                previous = None;
+                continue;
+            }
+
+            if code == end { break }
+
+            let entry = match table.get(code as usize) {
+                Some(entry) => entry.clone(),
+                None => {
+                    let previous = previous.clone().unwrap();
+                    [previous.as_slice(), &previous[..1]].concat()
+                },
+            };
+
+            if let Some(previous) = previous {
+                if table.len() < MAX_CODE as usize {
+                    table.push([previous.as_slice(), &entry[..1]].concat());
+                    if table.len() == 1 << code_size && code_size < 12 { code_size += 1 }
+                }
+            }
+
+            output.extend_from_slice(&entry);
+            previous = Some(entry);
+        }
+
+        (output, clears, widest)
+    }
+
+    fn frames(input: &str, length: usize) -> Vec<Vec<Position>> {
+        Bridge::<2>::new(length).record(&Motion::parse(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn one_frame_per_step_plus_the_start() {
+        assert_eq!(frames(EXAMPLE, 2).len(), 25);
+        assert_eq!(frames(EXAMPLE, 10).len(), 25);
+        assert_eq!(frames(LARGER_EXAMPLE, 10).len(), 97);
+    }
+
+    #[test]
+    fn bounds_cover_every_knot() {
+        for (input, length) in [(EXAMPLE, 2), (EXAMPLE, 10), (LARGER_EXAMPLE, 10)] {
+            let frames = frames(input, length);
+            let bounds = Bounds::of(&frames);
+
+            for &[x, y] in frames.iter().flatten().chain(&[[0, 0]]) {
+                assert!((bounds.min[0]..=bounds.max[0]).contains(&x), "{} outside {:?}", x, bounds);
+                assert!((bounds.min[1]..=bounds.max[1]).contains(&y), "{} outside {:?}", y, bounds);
+                assert!(bounds.index([x, y]) < bounds.width() * bounds.height());
+            }
+        }
+
+        let bounds = Bounds::of(&frames(LARGER_EXAMPLE, 10));
+        assert_eq!((bounds.min, bounds.max), ([-11, -5], [14, 15]));
+    }
+
+    #[test]
+    fn gif_is_framed_by_header_and_trailer() {
+        let frames = frames(EXAMPLE, 10);
+        let mut output = vec![];
+
+        write_gif(&mut output, &frames, &Bounds::of(&frames), 5, 3).unwrap();
+
+        assert!(output.starts_with(b"GIF89a"));
+        assert_eq!(output.last(), Some(&b';'));
+        assert_eq!(u16::from_le_bytes([output[6], output[7]]) as usize, Bounds::of(&frames).width() * 3);
+    }
+
+    #[test]
+    fn lzw_round_trips() {
+        assert_eq!(lzw_decode(&lzw_encode(&[])).0, []);
+        assert_eq!(lzw_decode(&lzw_encode(&[3])).0, [3]);
+
+        let repetitive = [0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 0, 0, 0].repeat(40);
+        let (decoded, clears, widest) = lzw_decode(&lzw_encode(&repetitive));
+        assert_eq!((decoded, clears), (repetitive, 1));
+        assert!(widest > MIN_CODE_SIZE + 1);
+
+        // Enough noise to fill the code table several times over.
+        let mut state = 0x9e37_79b9_u32;
+        let noise = (0..60_000).map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state % 5) as u8
+        }).collect::<Vec<_>>();
+
+        let (decoded, clears, widest) = lzw_decode(&lzw_encode(&noise));
+        assert_eq!(decoded, noise);
+        assert!(clears > 2);
+        assert_eq!(widest, 12);
+    }
+}
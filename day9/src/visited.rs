@@ -0,0 +1,99 @@
+use std::cmp::Ordering;
+
+const MIN_PENDING: usize = 4096;
+
+/// A set of locations kept as one sorted run plus an unsorted buffer of recent insertions.
+/// The buffer is sorted and merged into the run once it grows as large as the run itself,
+/// which keeps insertion amortised `O(log n)` while storing nothing but the locations.
+pub struct VisitedSet<T> {
+    sorted: Vec<T>,
+    pending: Vec<T>,
+    last: Option<T>,
+}
+
+impl<T: Copy + Ord> VisitedSet<T> {
+    pub fn new() -> VisitedSet<T> {
+        VisitedSet { sorted: vec![], pending: vec![], last: None }
+    }
+
+    pub fn insert(&mut self, location: T) {
+        if self.last == Some(location) { return }
+
+        self.last = Some(location);
+        self.pending.push(location);
+
+        if self.pending.len() >= self.sorted.len().max(MIN_PENDING) {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.pending.is_empty() { return }
+
+        self.pending.sort_unstable();
+        self.pending.dedup();
+
+        let mut merged = Vec::with_capacity(self.sorted.len() + self.pending.len());
+        let (mut sorted, mut pending) = (self.sorted.iter().peekable(), self.pending.iter().peekable());
+
+        while let (Some(&&a), Some(&&b)) = (sorted.peek(), pending.peek()) {
+            match a.cmp(&b) {
+                Ordering::Less      => { merged.push(a); sorted.next(); },
+                Ordering::Greater   => { merged.push(b); pending.next(); },
+                Ordering::Equal     => { merged.push(a); sorted.next(); pending.next(); },
+            }
+        }
+
+        merged.extend(sorted);
+        merged.extend(pending);
+
+        self.sorted = merged;
+        self.pending.clear();
+    }
+
+    /// The number of distinct locations, merging any pending insertions first.
+    pub fn count(&mut self) -> usize {
+        self.flush();
+        self.sorted.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn behaves_like_a_hash_set() {
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for range in [1, 50, 300, 5_000] {
+            let mut visited = VisitedSet::new();
+            let mut expected = HashSet::new();
+
+            // Long enough for several merges, with the small ranges repeating locations across them.
+            for step in 0..40_000 {
+                let location = [(next() % range) as i32 - range as i32 / 2, (next() % range) as i32];
+
+                visited.insert(location);
+                expected.insert(location);
+
+                if step % 9_999 == 0 { assert_eq!(visited.count(), expected.len()) }
+            }
+
+            let mut expected = expected.into_iter().collect::<Vec<_>>();
+            expected.sort_unstable();
+
+            assert_eq!(visited.count(), expected.len());
+            assert_eq!(visited.sorted, expected);
+            assert!(visited.pending.is_empty());
+        }
+    }
+}